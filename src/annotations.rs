@@ -0,0 +1,185 @@
+use std::{collections::HashMap, path::Path};
+
+use serde::Deserialize;
+
+use crate::{hotkey::Hotkey, sequence::parse_sequence};
+
+/// The description line is drawn smaller than the keys
+pub const SCALE: f32 = 0.6;
+
+/// The annotation file
+#[derive(Debug, Default, Deserialize)]
+struct AnnotationFile {
+    /// Chords like "Ctrl+Shift+P", or sequences like "C-x C-s", and what they do in every app
+    #[serde(default)]
+    global: HashMap<String, String>,
+    /// The same, for apps by their window class
+    #[serde(default)]
+    apps: HashMap<String, HashMap<String, String>>,
+    /// Vim commands like "ciw" or ":wq", over the built-in descriptions
+    #[serde(default)]
+    vim: HashMap<String, String>,
+}
+
+/// What chords do, shown under the keys when they're pressed
+#[derive(Debug, Clone, Default)]
+pub struct Annotations {
+    global: Vec<(Vec<Hotkey>, String)>,
+    /// By the lowercase window class
+    apps: HashMap<String, Vec<(Vec<Hotkey>, String)>>,
+    vim: HashMap<String, String>,
+}
+
+impl Annotations {
+    /// Reads an annotation file, a missing one has no annotations
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("Could not read {}: {e}", path.display())),
+        };
+
+        Self::parse(&source).map_err(|e| format!("Annotations in {}: {e}", path.display()))
+    }
+
+    fn parse(source: &str) -> Result<Self, String> {
+        let file: AnnotationFile = toml::from_str(source).map_err(|e| e.to_string())?;
+
+        let parse = |descriptions: HashMap<String, String>| {
+            descriptions
+                .into_iter()
+                .map(|(keys, description)| Ok((parse_sequence(&keys)?, description)))
+                .collect::<Result<Vec<_>, String>>()
+        };
+
+        Ok(Self {
+            global: parse(file.global)?,
+            apps: file
+                .apps
+                .into_iter()
+                .map(|(app, descriptions)| Ok((app.to_lowercase(), parse(descriptions)?)))
+                .collect::<Result<_, String>>()?,
+            vim: file.vim,
+        })
+    }
+
+    /// What the chord or sequence does in the first of `apps` that has it, or in every app
+    pub fn describe(&self, keys: &[Hotkey], apps: &[String]) -> Option<&str> {
+        apps.iter()
+            .filter_map(|app| self.apps.get(&app.to_lowercase()))
+            .find_map(|descriptions| find(descriptions, keys))
+            .or_else(|| find(&self.global, keys))
+    }
+
+    /// The line shown under the keys, like "C-x C-s — Save" for the sequence `name`
+    pub fn line(&self, name: &str, keys: &[Hotkey], apps: &[String]) -> Option<String> {
+        self.describe(keys, apps)
+            .map(|description| format!("{name} — {description}"))
+    }
+
+    /// The line for a Vim command, the `[vim]` table wins over Vim's own `description`
+    pub fn vim_line(&self, command: &str, description: Option<String>) -> Option<String> {
+        self.describe_vim(command)
+            .map(String::from)
+            .or(description)
+            .map(|description| format!("{command} — {description}"))
+    }
+
+    /// Some longer sequence starts with `keys`, in `apps` or in every app
+    pub fn continues(&self, keys: &[Hotkey], apps: &[String]) -> bool {
+        apps.iter()
+            .filter_map(|app| self.apps.get(&app.to_lowercase()))
+            .chain([&self.global])
+            .flatten()
+            .any(|(sequence, _)| sequence.len() > keys.len() && sequence.starts_with(keys))
+    }
+
+    /// Some chords are only described in some apps, so the focused one matters
+    pub fn has_apps(&self) -> bool {
+        !self.apps.is_empty()
+    }
+
+    /// What a Vim command like "ciw" does, if it's in the `[vim]` table
+    fn describe_vim(&self, command: &str) -> Option<&str> {
+        self.vim.get(command).map(String::as_str)
+    }
+}
+
+fn find<'a>(descriptions: &'a [(Vec<Hotkey>, String)], keys: &[Hotkey]) -> Option<&'a str> {
+    descriptions
+        .iter()
+        .find(|(sequence, _)| sequence == keys)
+        .map(|(_, description)| description.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(sequence: &str) -> Vec<Hotkey> {
+        parse_sequence(sequence).unwrap()
+    }
+
+    const FILE: &str = r#"
+        [global]
+        "Ctrl+S" = "Save"
+        "C-x C-f" = "Find file"
+
+        [apps.Code]
+        "Ctrl+S" = "Save all"
+
+        [vim]
+        ciw = "Change the word"
+    "#;
+
+    #[test]
+    fn apps_win_over_global() {
+        let annotations = Annotations::parse(FILE).unwrap();
+
+        assert_eq!(annotations.describe(&keys("Ctrl+S"), &[]), Some("Save"));
+        assert_eq!(
+            annotations.describe(&keys("Ctrl+S"), &["code".to_string()]),
+            Some("Save all")
+        );
+        assert_eq!(annotations.describe(&keys("Ctrl+Q"), &[]), None);
+    }
+
+    #[test]
+    fn sequences_are_continued() {
+        let annotations = Annotations::parse(FILE).unwrap();
+
+        assert!(annotations.continues(&keys("C-x"), &[]));
+        assert!(!annotations.continues(&keys("C-x C-f"), &[]));
+
+        assert_eq!(
+            annotations
+                .line("C-x C-f", &keys("C-x C-f"), &[])
+                .as_deref(),
+            Some("C-x C-f — Find file")
+        );
+    }
+
+    #[test]
+    fn vim_descriptions_are_replaced() {
+        let annotations = Annotations::parse(FILE).unwrap();
+
+        assert_eq!(
+            annotations
+                .vim_line("ciw", Some("change inner word".to_string()))
+                .as_deref(),
+            Some("ciw — Change the word")
+        );
+        assert_eq!(
+            annotations
+                .vim_line("dd", Some("delete line".to_string()))
+                .as_deref(),
+            Some("dd — delete line")
+        );
+        assert_eq!(annotations.vim_line("zz", None), None);
+    }
+
+    #[test]
+    fn bad_chords_are_errors() {
+        assert!(Annotations::parse("[global]\n\"Ctrl+Nope\" = \"x\"").is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use serde::Deserialize;
+
+#[cfg(target_os = "linux")]
+use crate::x11;
+use crate::{annotations::Annotations, history::Mode, labels::Labels};
+
+/// An `[apps.<name>]` section, used while the window with that class is focused
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AppConfig {
+    /// Also used while the focused window's title contains this
+    pub title: Option<String>,
+    pub mode: Option<Mode>,
+    pub labels: Option<HashMap<String, String>>,
+    /// Used instead of the annotation file
+    pub annotations: Option<PathBuf>,
+    /// Keys aren't shown or counted while the app is focused
+    pub hide: Option<bool>,
+}
+
+/// The options of an app, over the ones of the config
+#[derive(Debug, Clone)]
+pub struct App {
+    /// Lowercase
+    class: String,
+    /// Lowercase
+    title: Option<String>,
+    pub mode: Option<Mode>,
+    pub labels: Option<Labels>,
+    pub annotations: Option<Annotations>,
+    pub hide: bool,
+}
+
+impl App {
+    /// Loads the app's labels and annotations
+    pub fn load(name: &str, config: &AppConfig) -> Result<Self, String> {
+        let labels = config
+            .labels
+            .as_ref()
+            .map(|labels| {
+                Labels::parse(labels).map_err(|e| format!("Labels of [apps.{name}]: {e}"))
+            })
+            .transpose()?;
+
+        let annotations = config
+            .annotations
+            .as_ref()
+            .map(|path| Annotations::load(path))
+            .transpose()?;
+
+        Ok(Self {
+            mode: config.mode,
+            labels,
+            annotations,
+            ..Self::window(name, config)
+        })
+    }
+
+    /// Only what tells the app's window apart, and whether it's hidden
+    fn window(name: &str, config: &AppConfig) -> Self {
+        Self {
+            class: name.to_lowercase(),
+            title: config.title.as_ref().map(|title| title.to_lowercase()),
+            mode: None,
+            labels: None,
+            annotations: None,
+            hide: config.hide.unwrap_or_default(),
+        }
+    }
+
+    /// The app is focused, by one of its window class names or its title
+    pub fn matches(&self, apps: &[String], title: &str) -> bool {
+        apps.iter().any(|app| app.to_lowercase() == self.class)
+            || self
+                .title
+                .as_ref()
+                .is_some_and(|part| title.to_lowercase().contains(part.as_str()))
+    }
+}
+
+/// The apps with options of their own, and which of them is focused
+#[derive(Default)]
+pub struct Apps {
+    apps: Vec<App>,
+    #[cfg(target_os = "linux")]
+    focus: Option<x11::Focus>,
+    /// The class names of the window focused at the last key press
+    pub classes: Vec<String>,
+    pub title: String,
+    /// The one of `apps` that's focused
+    focused: Option<usize>,
+}
+
+impl Apps {
+    /// Connects to find the focused window, where that's possible
+    pub fn connect() -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            focus: x11::Focus::connect()
+                .map_err(|e| eprintln!("Could not find the focused app: {e}"))
+                .ok(),
+            ..Default::default()
+        }
+    }
+
+    pub fn set(&mut self, apps: Vec<App>) {
+        self.apps = apps;
+        self.focused = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.apps.is_empty()
+    }
+
+    pub fn knows_focus(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        return self.focus.is_some();
+
+        #[cfg(not(target_os = "linux"))]
+        false
+    }
+
+    /// Finds the focused window and which of the apps it is
+    pub fn update(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some(focus) = &self.focus {
+            let window = focus.window().unwrap_or_else(|e| {
+                eprintln!("Could not find the focused app: {e}");
+                Default::default()
+            });
+
+            self.classes = window.apps;
+            self.title = window.title;
+        }
+
+        self.focused = self
+            .apps
+            .iter()
+            .position(|app| app.matches(&self.classes, &self.title));
+    }
+
+    pub fn focused(&self) -> Option<&App> {
+        self.focused.and_then(|app| self.apps.get(app))
+    }
+}
+
+/// The apps that hide their keys, for the modes without a window
+pub struct HiddenApps {
+    apps: Vec<App>,
+    #[cfg(target_os = "linux")]
+    focus: Option<x11::Focus>,
+}
+
+impl HiddenApps {
+    pub fn new(apps: &BTreeMap<String, AppConfig>) -> Self {
+        let apps = Self::hidden(apps);
+
+        Self {
+            // the focused window is only asked for when there's something to hide
+            #[cfg(target_os = "linux")]
+            focus: (!apps.is_empty())
+                .then(|| {
+                    x11::Focus::connect()
+                        .map_err(|e| eprintln!("Could not find the focused app: {e}"))
+                        .ok()
+                })
+                .flatten(),
+            apps,
+        }
+    }
+
+    /// One of them is focused, so its keys are left out
+    pub fn are_focused(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        if let Some(focus) = &self.focus {
+            return match focus.window() {
+                Ok(window) => self.hide(&window.apps, &window.title),
+                Err(e) => {
+                    eprintln!("Could not find the focused app: {e}");
+                    false
+                }
+            };
+        }
+
+        false
+    }
+
+    fn hidden(apps: &BTreeMap<String, AppConfig>) -> Vec<App> {
+        apps.iter()
+            .map(|(name, app)| App::window(name, app))
+            .filter(|app| app.hide)
+            .collect()
+    }
+
+    /// The window with these class names and title is one of the apps
+    fn hide(&self, classes: &[String], title: &str) -> bool {
+        self.apps.iter().any(|app| app.matches(classes, title))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str, title: Option<&str>, hide: bool) -> App {
+        App::window(
+            name,
+            &AppConfig {
+                title: title.map(String::from),
+                hide: Some(hide),
+                ..Default::default()
+            },
+        )
+    }
+
+    fn classes(classes: &[&str]) -> Vec<String> {
+        classes.iter().map(|class| class.to_string()).collect()
+    }
+
+    #[test]
+    fn apps_match_by_class_or_title() {
+        let app = app("KeePassXC", Some("Passwords"), true);
+
+        assert!(app.matches(&classes(&["keepassxc", "KeePassXC"]), ""));
+        assert!(app.matches(&classes(&["KEEPASSXC"]), ""));
+        assert!(app.matches(&classes(&["firefox"]), "My passwords - Firefox"));
+
+        assert!(!app.matches(&classes(&["firefox"]), "Password manager reviews"));
+        assert!(!app.matches(&classes(&["keepass"]), ""));
+        assert!(!app.matches(&[], ""));
+    }
+
+    #[test]
+    fn apps_without_a_title_only_match_by_class() {
+        let app = app("code", None, false);
+
+        assert!(app.matches(&classes(&["Code"]), "main.rs"));
+        assert!(!app.matches(&classes(&["firefox"]), "code review"));
+    }
+
+    #[test]
+    fn only_hidden_apps_hide_keys() {
+        let configs = BTreeMap::from([
+            (
+                "keepassxc".to_string(),
+                AppConfig {
+                    hide: Some(true),
+                    ..Default::default()
+                },
+            ),
+            ("code".to_string(), AppConfig::default()),
+        ]);
+
+        let hidden = HiddenApps {
+            apps: HiddenApps::hidden(&configs),
+            #[cfg(target_os = "linux")]
+            focus: None,
+        };
+
+        assert!(hidden.hide(&classes(&["KeePassXC"]), ""));
+        assert!(!hidden.hide(&classes(&["code"]), ""));
+        // nothing is hidden without knowing the focused window
+        assert!(!hidden.are_focused());
+    }
+
+    #[test]
+    fn the_focused_app_is_found() {
+        let mut apps = Apps::default();
+        apps.set(vec![
+            app("code", None, false),
+            app("firefox", Some("YouTube"), true),
+        ]);
+
+        apps.classes = classes(&["Firefox"]);
+        apps.update();
+        assert!(apps.focused().is_some_and(|app| app.hide));
+
+        apps.classes = classes(&["kitty"]);
+        apps.title = "vim".to_string();
+        apps.update();
+        assert!(apps.focused().is_none());
+    }
+}
@@ -0,0 +1,54 @@
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::import::Format;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Don't open a window, print every key entry as a JSON line on stdout instead
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Use the options of this [profiles.<name>] section of the config
+    #[arg(long)]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Control the running overlay: pause, resume, clear, show-text "...",
+    /// set-mode <all|shortcuts>, set-profile <name>, reload-config, status,
+    /// export-heatmap [dir] or report
+    #[cfg(unix)]
+    Ctl {
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Capture keys on this machine and send them to the overlay running on another one
+    Send {
+        /// The `listen` address in the overlay's [remote] section, e.g. 192.168.1.10:8911
+        address: String,
+        /// The shared secret, defaults to the one in the [remote] section
+        #[arg(long)]
+        secret: Option<String>,
+    },
+    /// Turn VS Code, JetBrains or i3/sway keybindings into annotations
+    ImportKeymap {
+        /// keybindings.json, a JetBrains keymap .xml or an i3/sway config
+        file: PathBuf,
+        /// Guessed from the file name by default
+        #[arg(long, value_enum)]
+        format: Option<Format>,
+        /// Only describe the chords while this app is focused, by its window class
+        #[arg(long)]
+        app: Option<String>,
+        /// Add them to this annotation file instead of printing them
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
@@ -0,0 +1,389 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
+
+use serde::Deserialize;
+use toml::from_str;
+
+use crate::{
+    annotations::Annotations,
+    apps::{App, AppConfig},
+    fonts::Fonts,
+    history::Mode,
+    hotkey::Hotkey,
+    keyboard::{Board, KeyboardConfig},
+    keys::Fallback,
+    labels::Labels,
+    position::PositionConfig,
+    profiles,
+    remote::RemoteConfig,
+    report::ReportConfig,
+    sequence::{SequenceConfig, Sequences},
+    server::ServerConfig,
+    speed::SpeedConfig,
+    stack::{Layout, StackConfig},
+    theme::{Theme, ThemeConfig, UserTheme},
+    vim::VimConfig,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    pub position: Option<PositionConfig>,
+    pub font_size: Option<u32>,
+    pub font_path: Option<PathBuf>,
+    pub fallback_fonts: Option<Vec<PathBuf>>,
+    pub width: Option<u32>,
+    pub erase_on_backspace: Option<bool>,
+    pub mode: Option<Mode>,
+    pub layout: Option<Layout>,
+    pub stack: Option<StackConfig>,
+    pub keyboard: Option<KeyboardConfig>,
+    pub speed: Option<SpeedConfig>,
+    pub report: Option<ReportConfig>,
+    pub vim: Option<VimConfig>,
+    pub sequences: Option<SequenceConfig>,
+    /// The annotation file, annotations.toml next to the config by default
+    pub annotations: Option<PathBuf>,
+    pub server: Option<ServerConfig>,
+    pub remote: Option<RemoteConfig>,
+    pub capture_fallback: Option<Fallback>,
+    pub click_through: Option<bool>,
+    pub click_through_hotkey: Option<Hotkey>,
+    pub theme: Option<ThemeConfig>,
+    pub themes: Option<HashMap<String, UserTheme>>,
+    /// Labels by key name, like "Return" = "Enter"
+    pub labels: Option<HashMap<String, String>>,
+    /// Options used while an app is focused, by its window class
+    pub apps: Option<BTreeMap<String, AppConfig>>,
+    /// The one of `profiles` used at start
+    pub profile: Option<String>,
+    /// Options over the top-level ones, by profile name
+    pub profiles: Option<BTreeMap<String, toml::Value>>,
+    pub profile_hotkey: Option<Hotkey>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            position: Some(PositionConfig::default()),
+            font_size: Some(30),
+            font_path: None,
+            fallback_fonts: Some(Vec::new()),
+            width: Some(500),
+            erase_on_backspace: Some(false),
+            mode: Some(Mode::All),
+            layout: Some(Layout::Strip),
+            stack: Some(StackConfig::default()),
+            keyboard: Some(KeyboardConfig::default()),
+            speed: Some(SpeedConfig::default()),
+            report: Some(ReportConfig {
+                on_exit: Some(false),
+                dir: None,
+            }),
+            vim: Some(VimConfig::default()),
+            sequences: Some(SequenceConfig::default()),
+            annotations: None,
+            server: Some(ServerConfig::default()),
+            remote: Some(RemoteConfig::default()),
+            capture_fallback: Some(Fallback::None),
+            click_through: Some(false),
+            click_through_hotkey: Some("Ctrl+Alt+C".parse().unwrap()),
+            theme: Some(ThemeConfig::default()),
+            themes: Some(HashMap::new()),
+            labels: Some(HashMap::new()),
+            apps: Some(BTreeMap::new()),
+            profile: None,
+            profiles: Some(BTreeMap::new()),
+            profile_hotkey: Some("Ctrl+Alt+P".parse().unwrap()),
+        }
+    }
+}
+
+impl Config {
+    pub fn theme(&self) -> Result<Theme, String> {
+        let themes = self.themes.clone().unwrap_or_default();
+
+        Theme::resolve(&self.theme.clone().unwrap_or_default(), &themes)
+    }
+
+    pub fn font_paths(&self) -> FontPaths {
+        (
+            self.font_path.clone(),
+            self.fallback_fonts.clone().unwrap_or_default(),
+        )
+    }
+}
+
+/// The font file and the fallback ones
+pub type FontPaths = (Option<PathBuf>, Vec<PathBuf>);
+
+/// What the config reads from other files or parses, loaded before any of it is used
+pub struct Loaded {
+    pub board: Board,
+    /// Only if the font files changed, since loaded fonts are kept until the app exits
+    pub fonts: Option<(Fonts, FontPaths)>,
+    pub theme: Theme,
+    pub labels: Labels,
+    pub annotations: Annotations,
+    pub apps: Vec<App>,
+    pub sequences: Option<Sequences>,
+}
+
+impl Loaded {
+    /// Collects the errors instead of stopping at the first, with defaults for what failed,
+    /// fonts are loaded only if their paths changed from `current`
+    pub fn new(config: &Config, current: Option<&FontPaths>) -> (Self, Vec<String>) {
+        fn checked<T>(
+            result: Result<T, String>,
+            errors: &mut Vec<String>,
+            default: impl FnOnce() -> T,
+        ) -> T {
+            result.unwrap_or_else(|e| {
+                errors.push(e);
+                default()
+            })
+        }
+
+        let mut errors = Vec::new();
+
+        let layout = config
+            .keyboard
+            .clone()
+            .unwrap_or_default()
+            .layout
+            .unwrap_or_else(|| KeyboardConfig::default().layout.unwrap());
+
+        let board = checked(Board::load(&layout), &mut errors, || {
+            Board::load(&KeyboardConfig::default().layout.unwrap()).unwrap_or_default()
+        });
+
+        let theme = checked(config.theme(), &mut errors, Theme::default);
+
+        let labels = checked(
+            Labels::parse(&config.labels.clone().unwrap_or_default())
+                .map_err(|e| format!("Labels: {e}")),
+            &mut errors,
+            Labels::default,
+        );
+
+        let annotations = config
+            .annotations
+            .clone()
+            .unwrap_or_else(|| config_path().with_file_name("annotations.toml"));
+
+        let annotations = checked(
+            Annotations::load(&annotations),
+            &mut errors,
+            Annotations::default,
+        );
+
+        let apps = config
+            .apps
+            .iter()
+            .flatten()
+            .filter_map(|(name, app)| App::load(name, app).map_err(|e| errors.push(e)).ok())
+            .collect();
+
+        let sequences = Sequences::enabled(&config.sequences.clone().unwrap_or_default())
+            .unwrap_or_else(|e| {
+                errors.push(e);
+                None
+            });
+
+        let font_paths = config.font_paths();
+
+        let fonts = (current != Some(&font_paths)).then(|| {
+            let (fonts, font_errors) = Fonts::load(font_paths.0.as_deref(), &font_paths.1);
+            errors.extend(font_errors);
+
+            (fonts, font_paths)
+        });
+
+        let loaded = Self {
+            board,
+            fonts,
+            theme,
+            labels,
+            annotations,
+            apps,
+            sequences,
+        };
+
+        (loaded, errors)
+    }
+}
+
+pub fn config_path() -> PathBuf {
+    let config_dir = dirs::config_dir().expect("No Config Directory");
+
+    config_dir.join("zr-alshasha/config.toml")
+}
+
+/// Where exported files go
+pub fn data_dir() -> PathBuf {
+    let data_dir = dirs::data_dir().expect("No Data Directory");
+
+    data_dir.join("zr-alshasha")
+}
+
+/// Reads the config file, with the options of the profile over the top-level ones
+pub fn read_config(profile: Option<&str>) -> Result<Config, String> {
+    let mut config = read_config_table()?;
+
+    profiles::apply(&mut config, profile)?;
+
+    toml::Value::Table(config)
+        .try_into()
+        .map_err(|e| e.to_string())
+}
+
+/// Like `read_config`, but a profile that's gone leaves the top-level options,
+/// with a warning that says so
+pub fn read_config_or_base(profile: Option<&str>) -> Result<(Config, Option<String>), String> {
+    let mut config = read_config_table()?;
+
+    let warning = profiles::apply_or_base(&mut config, profile);
+
+    let config = toml::Value::Table(config)
+        .try_into()
+        .map_err(|e| e.to_string())?;
+
+    Ok((config, warning))
+}
+
+fn read_config_table() -> Result<toml::value::Table, String> {
+    let config_str = read_config_file().map_err(|e| e.to_string())?;
+
+    from_str(&config_str).map_err(|e| e.to_string())
+}
+
+/// The config file, empty if there's none yet
+fn read_config_file() -> std::io::Result<String> {
+    match std::fs::read_to_string(config_path()) {
+        Ok(config_str) => Ok(config_str),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes the position to the config file, into the profile's table if one is in use,
+/// keeping the rest of it as it was, returns what was written
+pub fn save_position(
+    x: i32,
+    y: i32,
+    profile: Option<&str>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let config_file = config_path();
+
+    let written = with_position(&read_config_file()?, x, y, profile)?;
+
+    if let Some(config_dir) = config_file.parent() {
+        std::fs::create_dir_all(config_dir)?;
+    }
+
+    std::fs::write(config_file, &written)?;
+
+    Ok(written)
+}
+
+/// The config `source` with the position set, keeping its comments and formatting
+fn with_position(
+    source: &str,
+    x: i32,
+    y: i32,
+    profile: Option<&str>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let mut document = source.parse::<toml_edit::DocumentMut>()?;
+
+    let base_anchored = document
+        .get("position")
+        .and_then(|position| position.get("anchor"))
+        .is_some();
+
+    let table = match profile {
+        Some(name) => document
+            .entry("profiles")
+            .or_insert_with(toml_edit::table)
+            .as_table_like_mut()
+            .ok_or("profiles is not a table")?
+            .entry(name)
+            .or_insert_with(toml_edit::table),
+        None => document.as_item_mut(),
+    };
+
+    let position = table
+        .as_table_like_mut()
+        .ok_or("the profile is not a table")?
+        .entry("position")
+        .or_insert_with(toml_edit::table)
+        .as_table_like_mut()
+        .ok_or("position is not a table")?;
+
+    position.insert("x", toml_edit::value(i64::from(x)));
+    position.insert("y", toml_edit::value(i64::from(y)));
+
+    // an anchor would win over the saved position, the profile turns off the top-level one
+    if profile.is_some() && base_anchored {
+        position.insert("anchor", toml_edit::value("none"));
+    } else {
+        position.remove("anchor");
+    }
+
+    Ok(document.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_position_goes_into_the_active_profile() {
+        let source = r#"# my overlay
+font_size = 40 # big
+
+[position]
+anchor = "bottom-right"
+
+[profiles.coding]
+mode = "shortcuts"
+"#;
+
+        let written = with_position(source, 120, -5, Some("coding")).unwrap();
+
+        assert!(written.contains("# my overlay"));
+        assert!(written.contains("font_size = 40 # big"));
+        assert!(written.contains("mode = \"shortcuts\""));
+
+        let config: toml::Value = from_str(&written).unwrap();
+
+        // the top-level position is left alone
+        assert_eq!(config["position"]["anchor"].as_str(), Some("bottom-right"));
+        assert!(config["position"].get("x").is_none());
+
+        let position = &config["profiles"]["coding"]["position"];
+        assert_eq!(position["x"].as_integer(), Some(120));
+        assert_eq!(position["y"].as_integer(), Some(-5));
+        assert_eq!(position["anchor"].as_str(), Some("none"));
+    }
+
+    #[test]
+    fn the_position_replaces_the_top_level_anchor() {
+        let source = "# comment\n[position]\nanchor = \"top\"\nmonitor = 1\n";
+
+        let written = with_position(source, 10, 20, None).unwrap();
+        let config: toml::Value = from_str(&written).unwrap();
+
+        assert!(written.contains("# comment"));
+        assert!(config["position"].get("anchor").is_none());
+        assert_eq!(config["position"]["monitor"].as_integer(), Some(1));
+        assert_eq!(config["position"]["x"].as_integer(), Some(10));
+        assert_eq!(config["position"]["y"].as_integer(), Some(20));
+    }
+
+    #[test]
+    fn a_config_that_does_not_parse_is_not_written() {
+        assert!(with_position("font_size = ", 0, 0, None).is_err());
+        assert!(with_position("profiles = 1", 0, 0, Some("coding")).is_err());
+    }
+}
@@ -0,0 +1,293 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use iced::{
+    futures::{
+        channel::{mpsc, oneshot},
+        executor, StreamExt,
+    },
+    Subscription,
+};
+use iced_native::subscription;
+
+use crate::history::Mode;
+
+/// How long a client waits for the app to answer
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Command {
+    Pause,
+    Resume,
+    Clear,
+    ShowText(String),
+    SetMode(Mode),
+    SetProfile(String),
+    ReloadConfig,
+    Status,
+    /// Writes the heatmap to a directory, the data directory if none is given
+    ExportHeatmap(Option<PathBuf>),
+    /// Writes the session report
+    Report,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (name, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = unquote(argument.trim());
+
+        if argument.is_empty() && ["set-mode", "set-profile"].contains(&name) {
+            return Err(format!("{name} needs an argument"));
+        }
+
+        match name {
+            "pause" => Ok(Command::Pause),
+            "resume" => Ok(Command::Resume),
+            "clear" => Ok(Command::Clear),
+            "show-text" => Ok(Command::ShowText(argument.to_string())),
+            "set-mode" => argument.parse().map(Command::SetMode),
+            "set-profile" => Ok(Command::SetProfile(argument.to_string())),
+            "reload-config" => Ok(Command::ReloadConfig),
+            "status" => Ok(Command::Status),
+            "report" => Ok(Command::Report),
+            "export-heatmap" => Ok(Command::ExportHeatmap(
+                (!argument.is_empty()).then(|| PathBuf::from(argument)),
+            )),
+            _ => Err(format!("unknown command \"{name}\"")),
+        }
+    }
+}
+
+fn unquote(text: &str) -> &str {
+    text.strip_prefix('"')
+        .and_then(|text| text.strip_suffix('"'))
+        .unwrap_or(text)
+}
+
+/// A command sent by a client, answered with [`Request::reply`]
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub command: Command,
+    /// Taken by the first reply, the messages holding the request are cloned
+    reply: Arc<Mutex<Option<oneshot::Sender<String>>>>,
+}
+
+impl Request {
+    /// Creates a request and the receiver its response arrives on,
+    /// which is canceled if the app drops the request without replying
+    pub fn new(command: Command) -> (Self, oneshot::Receiver<String>) {
+        let (reply, response) = oneshot::channel();
+
+        (
+            Self {
+                command,
+                reply: Arc::new(Mutex::new(Some(reply))),
+            },
+            response,
+        )
+    }
+
+    pub fn reply(&self, response: impl Into<String>) {
+        if let Some(reply) = self.reply.lock().unwrap().take() {
+            // the client may have given up waiting already
+            let _ = reply.send(response.into());
+        }
+    }
+
+    /// Answers with the files that were written, or why they weren't
+    pub fn reply_written<E: std::fmt::Display>(&self, written: Result<Vec<PathBuf>, E>) {
+        match written {
+            Ok(files) => {
+                let files: Vec<_> = files
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect();
+                self.reply(format!("wrote {}", files.join(", ")));
+            }
+            Err(e) => self.reply(format!("error: {e}")),
+        }
+    }
+}
+
+pub enum State {
+    Starting,
+    Ready(mpsc::UnboundedReceiver<Request>),
+    Finished,
+}
+
+/// `$XDG_RUNTIME_DIR/zr-alshasha.sock`, or the temp directory if it isn't set
+pub fn socket_path() -> PathBuf {
+    dirs::runtime_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("zr-alshasha.sock")
+}
+
+pub fn listen() -> Subscription<Request> {
+    struct Control;
+
+    subscription::unfold(
+        std::any::TypeId::of::<Control>(),
+        State::Starting,
+        |state| async move {
+            match state {
+                State::Starting => {
+                    let listener = match bind() {
+                        Ok(listener) => listener,
+                        Err(e) => {
+                            eprintln!("Could not open the control socket: {e}");
+                            return (None, State::Finished);
+                        }
+                    };
+
+                    let (sender, receiver) = mpsc::unbounded();
+
+                    std::thread::spawn(move || {
+                        for stream in listener.incoming().flatten() {
+                            let sender = sender.clone();
+                            std::thread::spawn(move || {
+                                if let Err(e) = handle(stream, sender) {
+                                    eprintln!("Control connection failed: {e}");
+                                }
+                            });
+                        }
+                    });
+
+                    (None, State::Ready(receiver))
+                }
+                State::Ready(mut requests) => match requests.next().await {
+                    Some(request) => (Some(request), State::Ready(requests)),
+                    None => (None, State::Finished),
+                },
+                State::Finished => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+fn bind() -> io::Result<UnixListener> {
+    let path = socket_path();
+
+    if UnixStream::connect(&path).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("{} is used by another instance", path.display()),
+        ));
+    }
+
+    // left over from an instance that didn't exit cleanly
+    let _ = std::fs::remove_file(&path);
+
+    UnixListener::bind(path)
+}
+
+fn handle(stream: UnixStream, sender: mpsc::UnboundedSender<Request>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+
+    for line in BufReader::new(stream).lines() {
+        let response = match line?.parse() {
+            Ok(command) => {
+                let (request, response) = Request::new(command);
+
+                sender
+                    .unbounded_send(request)
+                    .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "app is closing"))?;
+
+                executor::block_on(response).unwrap_or_else(|_| "error: no response".to_string())
+            }
+            Err(e) => format!("error: {e}"),
+        };
+
+        writeln!(writer, "{response}")?;
+    }
+
+    Ok(())
+}
+
+/// Sends a command to the running overlay and returns its response
+pub fn send(command: &str) -> io::Result<String> {
+    let mut stream = UnixStream::connect(socket_path())?;
+    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+
+    writeln!(stream, "{command}")?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+
+    Ok(response.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commands_are_parsed() {
+        let cases = [
+            ("pause", Command::Pause),
+            ("resume\n", Command::Resume),
+            ("  clear  ", Command::Clear),
+            (
+                "show-text hello there",
+                Command::ShowText("hello there".into()),
+            ),
+            ("set-mode shortcuts", Command::SetMode(Mode::Shortcuts)),
+            ("set-profile coding", Command::SetProfile("coding".into())),
+            ("reload-config", Command::ReloadConfig),
+            ("status", Command::Status),
+            ("report", Command::Report),
+            ("export-heatmap", Command::ExportHeatmap(None)),
+            (
+                "export-heatmap /tmp/heat",
+                Command::ExportHeatmap(Some(PathBuf::from("/tmp/heat"))),
+            ),
+        ];
+
+        for (line, command) in cases {
+            assert_eq!(line.parse(), Ok(command), "{line}");
+        }
+    }
+
+    #[test]
+    fn quotes_are_taken_off_arguments() {
+        assert_eq!(
+            r#"show-text "  be right back ""#.parse(),
+            Ok(Command::ShowText("  be right back ".into()))
+        );
+        assert_eq!(
+            r#"set-profile "live stream""#.parse(),
+            Ok(Command::SetProfile("live stream".into()))
+        );
+        // only quotes around the whole argument
+        assert_eq!(
+            r#"show-text say "hi""#.parse(),
+            Ok(Command::ShowText(r#"say "hi""#.into()))
+        );
+    }
+
+    #[test]
+    fn bad_commands_are_refused() {
+        assert_eq!(
+            "set-mode".parse::<Command>(),
+            Err("set-mode needs an argument".to_string())
+        );
+        assert_eq!(
+            "set-profile \"\"".parse::<Command>(),
+            Err("set-profile needs an argument".to_string())
+        );
+        assert!("set-mode some".parse::<Command>().is_err());
+        assert_eq!(
+            "restart now".parse::<Command>(),
+            Err("unknown command \"restart\"".to_string())
+        );
+        assert!("".parse::<Command>().is_err());
+    }
+}
@@ -0,0 +1,287 @@
+use iced::{
+    futures::{channel::mpsc, StreamExt},
+    Subscription,
+};
+use iced_native::subscription;
+use zbus::{
+    blocking::{connection, Connection},
+    fdo, interface,
+    object_server::SignalEmitter,
+};
+
+use crate::control::{self, Command};
+
+const NAME: &str = "org.zralshasha.Overlay";
+const PATH: &str = "/org/zralshasha/Overlay";
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    Ready(Service),
+    Request(control::Request),
+}
+
+pub enum State {
+    Starting,
+    Ready(mpsc::UnboundedReceiver<control::Request>),
+    Finished,
+}
+
+/// The `org.zralshasha.Overlay` object, its methods are forwarded to the app as control commands
+struct Overlay {
+    sender: mpsc::UnboundedSender<control::Request>,
+}
+
+impl Overlay {
+    /// Waits for the app's reply without holding up the bus
+    async fn send(&self, command: Command) -> fdo::Result<()> {
+        let (request, response) = control::Request::new(command);
+
+        self.sender
+            .unbounded_send(request)
+            .map_err(|_| fdo::Error::Failed("app is closing".to_string()))?;
+
+        let response = response
+            .await
+            .map_err(|_| fdo::Error::Failed("no response".to_string()))?;
+
+        match response.strip_prefix("error: ") {
+            Some(e) => Err(fdo::Error::Failed(e.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+#[interface(name = "org.zralshasha.Overlay")]
+impl Overlay {
+    async fn pause(&self) -> fdo::Result<()> {
+        self.send(Command::Pause).await
+    }
+
+    async fn resume(&self) -> fdo::Result<()> {
+        self.send(Command::Resume).await
+    }
+
+    async fn clear(&self) -> fdo::Result<()> {
+        self.send(Command::Clear).await
+    }
+
+    async fn show_text(&self, text: String) -> fdo::Result<()> {
+        self.send(Command::ShowText(text)).await
+    }
+
+    async fn set_profile(&self, name: String) -> fdo::Result<()> {
+        self.send(Command::SetProfile(name)).await
+    }
+
+    #[zbus(signal)]
+    async fn key_displayed(emitter: &SignalEmitter<'_>, label: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn paused(emitter: &SignalEmitter<'_>, paused: bool) -> zbus::Result<()>;
+}
+
+/// The session bus connection, used to emit the overlay signals
+#[derive(Debug, Clone)]
+pub struct Service {
+    connection: Connection,
+}
+
+impl Service {
+    pub fn key_displayed(&self, label: &str) {
+        self.emit("KeyDisplayed", &(label,));
+    }
+
+    pub fn paused(&self, paused: bool) {
+        self.emit("Paused", &(paused,));
+    }
+
+    fn emit<B>(&self, signal: &str, body: &B)
+    where
+        B: serde::Serialize + zbus::zvariant::DynamicType,
+    {
+        if let Err(e) = self
+            .connection
+            .emit_signal(None::<()>, PATH, NAME, signal, body)
+        {
+            eprintln!("Could not emit {signal}: {e}");
+        }
+    }
+}
+
+/// Takes the overlay's name on the bus and serves its object
+fn serve(
+    builder: connection::Builder<'_>,
+    sender: mpsc::UnboundedSender<control::Request>,
+) -> zbus::Result<Connection> {
+    builder
+        .name(NAME)?
+        .serve_at(PATH, Overlay { sender })?
+        .build()
+}
+
+pub fn listen() -> Subscription<Event> {
+    struct DBus;
+
+    subscription::unfold(
+        std::any::TypeId::of::<DBus>(),
+        State::Starting,
+        |state| async move {
+            match state {
+                State::Starting => {
+                    let (sender, receiver) = mpsc::unbounded();
+
+                    let connection =
+                        connection::Builder::session().and_then(|builder| serve(builder, sender));
+
+                    match connection {
+                        Ok(connection) => (
+                            Some(Event::Ready(Service { connection })),
+                            State::Ready(receiver),
+                        ),
+                        Err(e) => {
+                            eprintln!("Could not register on the session bus: {e}");
+                            (None, State::Finished)
+                        }
+                    }
+                }
+                State::Ready(mut requests) => match requests.next().await {
+                    Some(request) => (Some(Event::Request(request)), State::Ready(requests)),
+                    None => (None, State::Finished),
+                },
+                State::Finished => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Child, Command as Process, Stdio},
+        sync::mpsc as std_mpsc,
+    };
+
+    use iced::futures::executor;
+
+    use super::*;
+
+    /// A private session bus, stopped when dropped
+    struct Bus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl Bus {
+        /// None if dbus-daemon isn't installed
+        fn start() -> Option<Self> {
+            let mut daemon = Process::new("dbus-daemon")
+                .args(["--session", "--print-address", "--nofork"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+
+        fn connect(&self) -> connection::Builder<'_> {
+            connection::Builder::address(self.address.as_str()).unwrap()
+        }
+    }
+
+    impl Drop for Bus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// Serves the overlay on the bus, with an app that answers like the real one
+    /// and passes on the commands it got
+    fn serve_app(bus: &Bus) -> (Connection, std_mpsc::Receiver<Command>) {
+        let (sender, mut requests) = mpsc::unbounded();
+        let (received, commands) = std_mpsc::channel();
+
+        let overlay = serve(bus.connect(), sender).unwrap();
+
+        std::thread::spawn(move || {
+            while let Some(request) = executor::block_on(requests.next()) {
+                let _ = received.send(request.command.clone());
+
+                match &request.command {
+                    Command::SetProfile(name) if name == "missing" => {
+                        request.reply("error: unknown profile \"missing\"")
+                    }
+                    // dropped without a reply, like when the app is closing
+                    Command::ShowText(text) if text == "drop" => {}
+                    _ => request.reply("ok"),
+                }
+            }
+        });
+
+        (overlay, commands)
+    }
+
+    #[test]
+    fn methods_are_sent_to_the_app() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon isn't installed, skipping");
+            return;
+        };
+
+        let (_overlay, commands) = serve_app(&bus);
+        let client = bus.connect().build().unwrap();
+
+        let call = |method: &str| client.call_method(Some(NAME), PATH, Some(NAME), method, &());
+        let call_with = |method: &str, argument: &str| {
+            client.call_method(Some(NAME), PATH, Some(NAME), method, &(argument,))
+        };
+
+        call("Pause").unwrap();
+        call("Resume").unwrap();
+        call("Clear").unwrap();
+        call_with("ShowText", "hello").unwrap();
+        call_with("SetProfile", "stream").unwrap();
+
+        assert_eq!(
+            commands.try_iter().collect::<Vec<_>>(),
+            [
+                Command::Pause,
+                Command::Resume,
+                Command::Clear,
+                Command::ShowText("hello".to_string()),
+                Command::SetProfile("stream".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn errors_are_returned_to_the_caller() {
+        let Some(bus) = Bus::start() else {
+            eprintln!("dbus-daemon isn't installed, skipping");
+            return;
+        };
+
+        let (_overlay, _commands) = serve_app(&bus);
+        let client = bus.connect().build().unwrap();
+
+        let call_with = |method: &str, argument: &str| {
+            client
+                .call_method(Some(NAME), PATH, Some(NAME), method, &(argument,))
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert!(call_with("SetProfile", "missing").contains("unknown profile"));
+        assert!(call_with("ShowText", "drop").contains("no response"));
+    }
+}
@@ -0,0 +1,186 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use iced::Font;
+use ttf_parser::Face;
+
+use crate::{FONT, FONT_BYTES};
+
+/// Font files by their path, iced keeps fonts for as long as the app runs,
+/// so a font loaded again, like on every reload, uses the same copy
+static FILES: Mutex<BTreeMap<PathBuf, File>> = Mutex::new(BTreeMap::new());
+
+/// The name of a font file and its bytes
+type File = (&'static str, &'static [u8]);
+
+/// A font and the glyphs it has
+#[derive(Clone)]
+struct Loaded {
+    font: Font,
+    bytes: &'static [u8],
+    face: Face<'static>,
+}
+
+impl Loaded {
+    fn embedded() -> Self {
+        Self {
+            font: FONT,
+            bytes: FONT_BYTES,
+            face: Face::from_slice(FONT_BYTES, 0).expect("the embedded font is valid"),
+        }
+    }
+
+    fn from_file(path: &Path) -> Result<Self, String> {
+        let error =
+            |e: &dyn std::fmt::Display| format!("Could not load font {}: {e}", path.display());
+
+        let mut files = FILES.lock().unwrap();
+
+        let (name, bytes) = match files.get(path) {
+            Some(file) => *file,
+            None => {
+                let bytes = std::fs::read(path).map_err(|e| error(&e))?;
+
+                // only fonts that can be used are kept
+                Face::from_slice(&bytes, 0).map_err(|e| error(&e))?;
+
+                let file = (
+                    &*Box::leak(path.display().to_string().into_boxed_str()),
+                    &*Box::leak(bytes.into_boxed_slice()),
+                );
+
+                files.insert(path.to_path_buf(), file);
+                file
+            }
+        };
+
+        let face = Face::from_slice(bytes, 0).map_err(|e| error(&e))?;
+
+        Ok(Self {
+            font: Font::External { name, bytes },
+            bytes,
+            face,
+        })
+    }
+
+    /// Same as the renderer, which scales the font so ascent to descent is `size`
+    fn measure(&self, text: &str, size: f32) -> f32 {
+        let height = f32::from(self.face.ascender()) - f32::from(self.face.descender());
+
+        let advances: f32 = text
+            .chars()
+            .map(|char| {
+                let glyph = self.face.glyph_index(char).unwrap_or_default();
+                f32::from(self.face.glyph_hor_advance(glyph).unwrap_or_default())
+            })
+            .sum();
+
+        advances * size / height
+    }
+
+    fn covers(&self, text: &str) -> bool {
+        text.chars()
+            .filter(|char| !char.is_whitespace())
+            .all(|char| {
+                // some fonts map missing glyphs to the empty glyph 0
+                matches!(self.face.glyph_index(char), Some(id) if id.0 != 0)
+            })
+    }
+}
+
+/// The configured font and its fallbacks, the embedded font is always the last fallback
+#[derive(Clone)]
+pub struct Fonts {
+    fonts: Vec<Loaded>,
+}
+
+impl Default for Fonts {
+    fn default() -> Self {
+        Self {
+            fonts: vec![Loaded::embedded()],
+        }
+    }
+}
+
+impl Fonts {
+    /// Loads the fonts, skipping the ones that can't be loaded with an error for each
+    pub fn load(font_path: Option<&Path>, fallback: &[PathBuf]) -> (Self, Vec<String>) {
+        let mut fonts = Vec::new();
+        let mut errors = Vec::new();
+
+        for path in font_path
+            .into_iter()
+            .chain(fallback.iter().map(PathBuf::as_path))
+        {
+            match Loaded::from_file(path) {
+                Ok(font) => fonts.push(font),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        fonts.push(Loaded::embedded());
+
+        (Self { fonts }, errors)
+    }
+
+    /// The first font that has every glyph of `text`, or the main font if none has
+    pub fn for_text(&self, text: &str) -> Font {
+        self.loaded_for(text).font
+    }
+
+    /// The width of `text` drawn with its font at `size`
+    pub fn measure(&self, text: &str, size: f32) -> f32 {
+        self.loaded_for(text).measure(text, size)
+    }
+
+    /// The file of the font `text` is drawn with
+    pub fn bytes_for(&self, text: &str) -> &'static [u8] {
+        self.loaded_for(text).bytes
+    }
+
+    fn loaded_for(&self, text: &str) -> &Loaded {
+        self.fonts
+            .iter()
+            .find(|font| font.covers(text))
+            .unwrap_or(&self.fonts[0])
+    }
+
+    /// The font files, in the order they are tried
+    pub fn bytes(&self) -> Vec<&'static [u8]> {
+        self.fonts.iter().map(|font| font.bytes).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn font_files_are_kept_once() {
+        let dir = std::env::temp_dir().join(format!("zr-alshasha-fonts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("font.ttf");
+        std::fs::write(&path, FONT_BYTES).unwrap();
+
+        let first = Loaded::from_file(&path).unwrap();
+        let again = Loaded::from_file(&path).unwrap();
+
+        assert!(std::ptr::eq(first.bytes, again.bytes));
+
+        let broken = dir.join("broken.ttf");
+        std::fs::write(&broken, b"not a font").unwrap();
+
+        assert!(Loaded::from_file(&broken).is_err());
+        assert!(!FILES.lock().unwrap().contains_key(&broken));
+
+        let (fonts, errors) = Fonts::load(Some(&path), &[broken, dir.join("missing.ttf")]);
+        assert_eq!(fonts.bytes().len(), 2);
+        assert_eq!(errors.len(), 2);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
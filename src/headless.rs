@@ -0,0 +1,78 @@
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+use crate::{
+    apps::HiddenApps,
+    config::Config,
+    history::{Change, History, KeyEntry},
+    keys::{rdev_to_key, Keys},
+    labels::Labels,
+};
+
+/// A line printed on stdout for every processed key press
+#[derive(Serialize)]
+struct Line<'a> {
+    action: Change,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<String>,
+    #[serde(flatten)]
+    entry: Option<&'a KeyEntry>,
+}
+
+/// Runs the same key pipeline as the window, but prints the entries as JSON lines
+pub fn run(config: &Config) -> Result<(), rdev::ListenError> {
+    let mut history = History::new(config.erase_on_backspace.unwrap_or_default());
+
+    let labels = config
+        .labels
+        .as_ref()
+        .map(|labels| {
+            Labels::parse(labels).unwrap_or_else(|e| {
+                eprintln!("Labels: {e}");
+                Labels::default()
+            })
+        })
+        .unwrap_or_default();
+
+    let hidden = HiddenApps::new(&config.apps.clone().unwrap_or_default());
+
+    rdev::listen(move |event| {
+        let change = match event.event_type {
+            // releases still go through, so no key is left held
+            rdev::EventType::KeyPress(_) if hidden.are_focused() => None,
+            rdev::EventType::KeyPress(key) => history.press(rdev_to_key(&key), key.modifier()),
+            rdev::EventType::KeyRelease(key) => {
+                history.release(key.modifier());
+                None
+            }
+            _ => None,
+        };
+
+        let Some(action) = change else {
+            return;
+        };
+
+        let entry = match action {
+            Change::Erased => None,
+            _ => history.last(),
+        };
+
+        let line = Line {
+            action,
+            label: entry.map(|entry| entry.label(&labels)),
+            entry,
+        };
+
+        let mut stdout = io::stdout().lock();
+
+        let written = serde_json::to_writer(&mut stdout, &line)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(stdout));
+
+        // whoever was reading our output is gone
+        if written.is_err() {
+            std::process::exit(0);
+        }
+    })
+}
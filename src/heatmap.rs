@@ -0,0 +1,383 @@
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ab_glyph::{point, Font as _, FontRef, PxScale, ScaleFont as _};
+use iced::{Color, Point, Rectangle};
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+
+use crate::{
+    fonts::Fonts,
+    keyboard::{Board, LABEL_SCALE},
+    keys::rdev_to_key,
+    theme::Theme,
+};
+
+/// The color of the most pressed key
+const HOT: Color = Color {
+    r: 0.9,
+    g: 0.3,
+    b: 0.2,
+    a: 1.0,
+};
+
+/// Size of a one unit key in the exported image, in pixels
+const PNG_KEY_SIZE: u16 = 64;
+
+/// How often every key was pressed this session
+#[derive(Debug, Clone, Default)]
+pub struct Heatmap {
+    /// By the rdev name of the key, like "KeyQ"
+    counts: BTreeMap<String, Count>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Count {
+    label: String,
+    count: u64,
+}
+
+/// A line of the exported CSV and JSON
+#[derive(Serialize)]
+struct Row<'a> {
+    key: &'a str,
+    label: &'a str,
+    count: u64,
+}
+
+fn name(key: &rdev::Key) -> String {
+    format!("{key:?}")
+}
+
+impl Heatmap {
+    pub fn press(&mut self, key: &rdev::Key) {
+        self.counts
+            .entry(name(key))
+            .or_insert_with(|| Count {
+                label: rdev_to_key(key),
+                count: 0,
+            })
+            .count += 1;
+    }
+
+    pub fn count(&self, key: &rdev::Key) -> u64 {
+        self.counts.get(&name(key)).map_or(0, |count| count.count)
+    }
+
+    /// From 0 for keys that weren't pressed to 1 for the most pressed one
+    pub fn heat(&self, key: &rdev::Key) -> f32 {
+        let max = self
+            .counts
+            .values()
+            .map(|count| count.count)
+            .max()
+            .unwrap_or_default();
+
+        if max == 0 {
+            return 0.0;
+        }
+
+        // so a few presses still show next to the space bar
+        (self.count(key) as f32 / max as f32).sqrt()
+    }
+
+    /// `cold` mixed with red by how hot the key is
+    pub fn color(&self, key: &rdev::Key, cold: Color) -> Color {
+        let heat = self.heat(key);
+        let mix = |cold: f32, hot: f32| cold + (hot - cold) * heat;
+
+        Color {
+            r: mix(cold.r, HOT.r),
+            g: mix(cold.g, HOT.g),
+            b: mix(cold.b, HOT.b),
+            a: mix(cold.a, HOT.a),
+        }
+    }
+
+    /// The most pressed keys first
+    fn rows(&self) -> Vec<Row<'_>> {
+        let mut rows: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(key, count)| Row {
+                key,
+                label: &count.label,
+                count: count.count,
+            })
+            .collect();
+
+        rows.sort_by_key(|row| std::cmp::Reverse(row.count));
+
+        rows
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("key,label,count\n");
+
+        for row in self.rows() {
+            csv += &format!(
+                "{},{},{}\n",
+                csv_field(row.key),
+                csv_field(row.label),
+                row.count
+            );
+        }
+
+        csv
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.rows())
+    }
+
+    /// The board with every key colored by how often it was pressed, and its count
+    pub fn to_png(&self, board: &Board, theme: &Theme, fonts: &Fonts) -> RgbaImage {
+        let margin = u32::from(PNG_KEY_SIZE / 4);
+
+        let mut image = RgbaImage::from_pixel(
+            board.width(PNG_KEY_SIZE) + margin * 2,
+            board.height(PNG_KEY_SIZE) + margin * 2,
+            rgba(theme.background),
+        );
+
+        let origin = Point::new(margin as f32, margin as f32);
+        let size = f32::from(PNG_KEY_SIZE) * LABEL_SCALE;
+        let caps = &theme.caps;
+
+        for board_key in board.keys() {
+            let bounds = board_key.bounds(origin, PNG_KEY_SIZE);
+            let count = self.count(&board_key.key);
+
+            fill_rounded(
+                &mut image,
+                bounds,
+                size * caps.border_radius,
+                rgba(self.color(&board_key.key, caps.key.background)),
+            );
+
+            let padding = size * caps.padding;
+
+            draw_text(
+                &mut image,
+                fonts.bytes_for(&board_key.label),
+                &board_key.label,
+                Point::new(bounds.x + padding, bounds.y + padding),
+                size,
+                bounds.width - padding * 2.0,
+                rgba(caps.key.text_color),
+            );
+
+            if count > 0 {
+                let count = count.to_string();
+                let size = size * 0.7;
+
+                draw_text(
+                    &mut image,
+                    fonts.bytes_for(&count),
+                    &count,
+                    Point::new(
+                        bounds.x + padding,
+                        bounds.y + bounds.height - padding - size,
+                    ),
+                    size,
+                    bounds.width - padding * 2.0,
+                    rgba(caps.key.text_color),
+                );
+            }
+        }
+
+        image
+    }
+
+    /// Writes the heatmap as PNG, CSV and JSON to `dir`, returns the written files
+    pub fn export(
+        &self,
+        dir: &Path,
+        board: &Board,
+        theme: &Theme,
+        fonts: &Fonts,
+    ) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        std::fs::create_dir_all(dir)?;
+
+        let time = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let path = |extension: &str| dir.join(format!("heatmap-{time}.{extension}"));
+
+        let png = path("png");
+        self.to_png(board, theme, fonts).save(&png)?;
+
+        let csv = path("csv");
+        std::fs::write(&csv, self.to_csv())?;
+
+        let json = path("json");
+        std::fs::write(&json, self.to_json()?)?;
+
+        Ok(vec![png, csv, json])
+    }
+}
+
+/// Quoted, so labels like "," don't split the line
+fn csv_field(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+fn rgba(color: Color) -> Rgba<u8> {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+    Rgba([
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        channel(color.a),
+    ])
+}
+
+/// Draws `color` over the pixel with `coverage` of it
+fn blend(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>, coverage: f32) {
+    if x < 0 || y < 0 || x >= i64::from(image.width()) || y >= i64::from(image.height()) {
+        return;
+    }
+
+    let pixel = image.get_pixel_mut(x as u32, y as u32);
+    let alpha = f32::from(color[3]) / 255.0 * coverage.clamp(0.0, 1.0);
+
+    for channel in 0..3 {
+        let below = f32::from(pixel[channel]);
+        pixel[channel] = (below + (f32::from(color[channel]) - below) * alpha).round() as u8;
+    }
+
+    let below = f32::from(pixel[3]) / 255.0;
+    pixel[3] = ((below + alpha * (1.0 - below)) * 255.0).round() as u8;
+}
+
+fn fill_rounded(image: &mut RgbaImage, bounds: Rectangle, radius: f32, color: Rgba<u8>) {
+    let radius = radius.min(bounds.width / 2.0).min(bounds.height / 2.0);
+
+    for y in bounds.y as i64..(bounds.y + bounds.height).ceil() as i64 {
+        for x in bounds.x as i64..(bounds.x + bounds.width).ceil() as i64 {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+
+            // distance past the rounded corner, if the pixel is in one
+            let dx = (bounds.x + radius - px).max(px - (bounds.x + bounds.width - radius));
+            let dy = (bounds.y + radius - py).max(py - (bounds.y + bounds.height - radius));
+
+            let coverage = if dx > 0.0 && dy > 0.0 {
+                radius + 0.5 - (dx * dx + dy * dy).sqrt()
+            } else {
+                1.0
+            };
+
+            blend(image, x, y, color, coverage);
+        }
+    }
+}
+
+/// Draws `text` with its top left at `position`, shrunk to fit `max_width`
+fn draw_text(
+    image: &mut RgbaImage,
+    font: &[u8],
+    text: &str,
+    position: Point,
+    size: f32,
+    max_width: f32,
+    color: Rgba<u8>,
+) {
+    let Ok(font) = FontRef::try_from_slice(font) else {
+        return;
+    };
+
+    let width = |size: f32| {
+        let scaled = font.as_scaled(PxScale::from(size));
+        text.chars()
+            .map(|char| scaled.h_advance(scaled.glyph_id(char)))
+            .sum::<f32>()
+    };
+
+    let size = match width(size) {
+        width if width > max_width && width > 0.0 => size * max_width / width,
+        _ => size,
+    };
+
+    let scaled = font.as_scaled(PxScale::from(size));
+    let mut x = position.x;
+
+    for char in text.chars() {
+        let id = scaled.glyph_id(char);
+        let glyph = id.with_scale_and_position(size, point(x, position.y + scaled.ascent()));
+
+        x += scaled.h_advance(id);
+
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+
+        let bounds = outline.px_bounds();
+
+        outline.draw(|gx, gy, coverage| {
+            blend(
+                image,
+                (bounds.min.x as i64) + i64::from(gx),
+                (bounds.min.y as i64) + i64::from(gy),
+                color,
+                coverage,
+            );
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A pressed 4 times, the comma once
+    fn heatmap() -> Heatmap {
+        let mut heatmap = Heatmap::default();
+
+        for _ in 0..4 {
+            heatmap.press(&rdev::Key::KeyA);
+        }
+        heatmap.press(&rdev::Key::Comma);
+
+        heatmap
+    }
+
+    #[test]
+    fn heat_is_relative_to_the_most_pressed_key() {
+        assert_eq!(Heatmap::default().heat(&rdev::Key::KeyA), 0.0);
+
+        let heatmap = heatmap();
+
+        assert_eq!(heatmap.heat(&rdev::Key::KeyA), 1.0);
+        assert_eq!(heatmap.heat(&rdev::Key::Comma), 0.5);
+        assert_eq!(heatmap.heat(&rdev::Key::KeyB), 0.0);
+
+        let cold = Color::from_rgb(0.0, 0.0, 1.0);
+        assert_eq!(rgba(heatmap.color(&rdev::Key::KeyA, cold)), rgba(HOT));
+        assert_eq!(heatmap.color(&rdev::Key::KeyB, cold), cold);
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(
+            heatmap().to_csv(),
+            "key,label,count\n\"KeyA\",\"A\",4\n\"Comma\",\",\",1\n"
+        );
+        assert_eq!(csv_field(r#"say "hi""#), r#""say ""hi""""#);
+    }
+
+    #[test]
+    fn json_has_the_most_pressed_keys_first() {
+        let json: serde_json::Value = serde_json::from_str(&heatmap().to_json().unwrap()).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "key": "KeyA", "label": "A", "count": 4 },
+                { "key": "Comma", "label": ",", "count": 1 },
+            ])
+        );
+    }
+}
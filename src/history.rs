@@ -0,0 +1,557 @@
+use std::{
+    collections::{BTreeSet, VecDeque},
+    fmt,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    keys::{Modifier, BACK_SPACE},
+    labels::Labels,
+};
+
+/// How many entries are kept before the oldest ones are dropped.
+const CAPACITY: usize = 64;
+
+/// A key (or chord) as it is shown, with how many times in a row it was pressed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KeyEntry {
+    pub key: String,
+    pub modifiers: Vec<Modifier>,
+    pub kind: EntryKind,
+    pub repeat: u32,
+    /// Which line of the stack layout the entry is on
+    #[serde(skip)]
+    pub line: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    Key,
+    /// Modifiers pressed without a key yet
+    Modifier,
+    /// A key pressed while holding modifiers
+    Chord,
+    /// A Vim command like "ciw" or ":wq"
+    Command,
+}
+
+/// Whether a key held with `modifiers` is a shortcut rather than typed text
+pub fn is_shortcut<'a>(modifiers: impl IntoIterator<Item = &'a Modifier>) -> bool {
+    modifiers
+        .into_iter()
+        .any(|modifier| *modifier != Modifier::Shift)
+}
+
+impl KeyEntry {
+    fn new(key: String, modifiers: Vec<Modifier>, kind: EntryKind, line: u32) -> Self {
+        Self {
+            key,
+            modifiers,
+            kind,
+            repeat: 1,
+            line,
+        }
+    }
+
+    /// The key with its modifiers, e.g. "דּ+C"
+    pub fn label(&self, labels: &Labels) -> String {
+        self.modifiers
+            .iter()
+            .map(|modifier| labels.get(modifier.label()))
+            .chain(std::iter::once(labels.get(&self.key)))
+            .collect::<Vec<_>>()
+            .join("+")
+    }
+
+    /// Commands, and chords with a modifier other than shift, which only changes the typed letter
+    pub fn is_shortcut(&self) -> bool {
+        self.kind == EntryKind::Command || is_shortcut(&self.modifiers)
+    }
+
+    fn same_keys(&self, other: &KeyEntry) -> bool {
+        self.key == other.key
+            && self.modifiers == other.modifiers
+            && self.kind == other.kind
+            && self.line == other.line
+    }
+}
+
+impl fmt::Display for KeyEntry {
+    /// Keys pressed more than 3 times in a row are shown as <key>...x<times>
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = self.label(&Labels::default());
+
+        if self.repeat > 3 {
+            return write!(f, "{label}...x{}", self.repeat);
+        }
+
+        let labels = vec![label; self.repeat as usize];
+        write!(f, "{}", labels.join(" "))
+    }
+}
+
+/// Which entries are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mode {
+    /// Every key
+    #[default]
+    All,
+    /// Only shortcuts, so typed text isn't shown
+    Shortcuts,
+}
+
+impl Mode {
+    pub fn shows(&self, entry: &KeyEntry) -> bool {
+        match self {
+            Mode::All => true,
+            Mode::Shortcuts => entry.is_shortcut(),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "all" => Ok(Mode::All),
+            "shortcuts" => Ok(Mode::Shortcuts),
+            _ => Err(format!("unknown mode \"{mode}\"")),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mode::All => write!(f, "all"),
+            Mode::Shortcuts => write!(f, "shortcuts"),
+        }
+    }
+}
+
+/// What happened to the history after a key press
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Change {
+    /// A new entry was added at the end
+    Pushed,
+    /// The last entry got a new repeat count or became a chord
+    Updated,
+    /// The last entry was removed
+    Erased,
+}
+
+/// Turns key presses into entries, detecting chords and collapsing repeated keys
+#[derive(Default)]
+pub struct History {
+    entries: VecDeque<KeyEntry>,
+    held: BTreeSet<Modifier>,
+    /// The last entry only has modifiers that are still held,
+    /// so the next key completes it into a chord
+    chord_pending: bool,
+    erase_on_backspace: bool,
+    /// The line new entries go on
+    line: u32,
+}
+
+impl History {
+    pub fn new(erase_on_backspace: bool) -> Self {
+        Self {
+            erase_on_backspace,
+            ..Default::default()
+        }
+    }
+
+    pub fn press(&mut self, key: String, modifier: Option<Modifier>) -> Option<Change> {
+        if let Some(modifier) = modifier {
+            // held modifiers are auto repeated, only the first press counts
+            if !self.held.insert(modifier) {
+                return None;
+            }
+
+            let others = self.held.iter().filter(|m| **m != modifier).copied();
+            let entry = KeyEntry::new(key, others.collect(), EntryKind::Modifier, self.line);
+
+            let change = self.complete_chord(entry);
+            self.chord_pending = true;
+            return Some(change);
+        }
+
+        let modifiers: Vec<_> = self.held.iter().copied().collect();
+
+        let kind = if modifiers.is_empty() {
+            EntryKind::Key
+        } else {
+            EntryKind::Chord
+        };
+
+        let entry = KeyEntry::new(key, modifiers, kind, self.line);
+
+        // if erase_on_backspace is specified as true in the config file
+        // erase the last key when pressing backspace
+        if self.erase_on_backspace && entry.key == BACK_SPACE && entry.modifiers.is_empty() {
+            return self.erase();
+        }
+
+        let change = self.complete_chord(entry);
+        self.chord_pending = false;
+        Some(change)
+    }
+
+    /// Adds a command, in place of the modifiers held to type it
+    pub fn push_command(&mut self, command: String) -> Change {
+        let dropped = self.drop_pending();
+
+        match self.push(KeyEntry::new(
+            command,
+            Vec::new(),
+            EntryKind::Command,
+            self.line,
+        )) {
+            Change::Pushed if dropped.is_some() => Change::Updated,
+            change => change,
+        }
+    }
+
+    /// Replaces the last command with a longer one, like "C-x" with "C-x C-s"
+    pub fn extend_command(&mut self, command: String) -> Change {
+        self.drop_pending();
+
+        match self.entries.back_mut() {
+            Some(last) if last.kind == EntryKind::Command && last.repeat == 1 => {
+                last.key = command;
+                Change::Updated
+            }
+            Some(last) if last.kind == EntryKind::Command => {
+                last.repeat -= 1;
+                self.push(KeyEntry::new(
+                    command,
+                    Vec::new(),
+                    EntryKind::Command,
+                    self.line,
+                ))
+            }
+            _ => self.push_command(command),
+        }
+    }
+
+    /// Removes the modifiers held for a key that isn't shown,
+    /// like shift for a capital letter typed in Vim's insert mode
+    pub fn drop_pending(&mut self) -> Option<Change> {
+        if !self.chord_pending
+            || self
+                .last()
+                .is_none_or(|last| last.kind != EntryKind::Modifier)
+        {
+            return None;
+        }
+
+        self.chord_pending = false;
+        self.erase()
+    }
+
+    pub fn release(&mut self, modifier: Option<Modifier>) {
+        if let Some(modifier) = modifier {
+            self.held.remove(&modifier);
+        }
+
+        if self.held.is_empty() {
+            self.chord_pending = false;
+        }
+    }
+
+    pub fn entries(&self) -> &VecDeque<KeyEntry> {
+        &self.entries
+    }
+
+    pub fn last(&self) -> Option<&KeyEntry> {
+        self.entries.back()
+    }
+
+    pub fn pop_front(&mut self) -> Option<KeyEntry> {
+        self.entries.pop_front()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.chord_pending = false;
+    }
+
+    /// Puts the next entries on a new line, unless the current one is still empty
+    pub fn break_line(&mut self) {
+        if self.last().is_some_and(|last| last.line == self.line) {
+            self.line += 1;
+        }
+    }
+
+    /// Moves the last entry to a new line, for when its line got too long
+    pub fn wrap_last(&mut self) {
+        self.line += 1;
+
+        if let Some(last) = self.entries.back_mut() {
+            last.line = self.line;
+        }
+    }
+
+    /// Drops the oldest lines, keeping the last `lines` of those `mode` shows,
+    /// returns whether any were dropped
+    pub fn keep_lines(&mut self, lines: usize, mode: Mode) -> bool {
+        let mut shown_lines: Vec<u32> = self
+            .entries
+            .iter()
+            .rev()
+            .filter(|entry| mode.shows(entry))
+            .map(|entry| entry.line)
+            .collect();
+
+        shown_lines.dedup();
+
+        let Some(&first_kept) = shown_lines.get(lines.max(1) - 1) else {
+            return false;
+        };
+
+        let dropped = self
+            .entries
+            .iter()
+            .any(|entry| entry.line < first_kept && mode.shows(entry));
+
+        while self
+            .entries
+            .front()
+            .is_some_and(|entry| entry.line < first_kept)
+        {
+            self.entries.pop_front();
+        }
+
+        dropped
+    }
+
+    pub fn set_erase_on_backspace(&mut self, erase_on_backspace: bool) {
+        self.erase_on_backspace = erase_on_backspace;
+    }
+
+    /// Replaces the pending modifiers with the chord they are part of,
+    /// otherwise adds the entry normally
+    fn complete_chord(&mut self, entry: KeyEntry) -> Change {
+        if self.chord_pending && !entry.modifiers.is_empty() {
+            if let Some(last) = self.entries.back_mut() {
+                if last.repeat == 1 {
+                    *last = entry;
+                    return Change::Updated;
+                }
+                last.repeat -= 1;
+            }
+        }
+
+        self.push(entry)
+    }
+
+    fn push(&mut self, entry: KeyEntry) -> Change {
+        if let Some(last) = self.entries.back_mut() {
+            if last.same_keys(&entry) {
+                last.repeat += 1;
+                return Change::Updated;
+            }
+        }
+
+        self.entries.push_back(entry);
+
+        if self.entries.len() > CAPACITY {
+            self.entries.pop_front();
+        }
+
+        Change::Pushed
+    }
+
+    fn erase(&mut self) -> Option<Change> {
+        let last = self.entries.back_mut()?;
+
+        if last.repeat > 1 {
+            last.repeat -= 1;
+            return Some(Change::Updated);
+        }
+
+        self.entries.pop_back();
+        Some(Change::Erased)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(history: &mut History, key: &str) -> Option<Change> {
+        history.press(key.to_string(), None)
+    }
+
+    fn hold(history: &mut History, modifier: Modifier) -> Option<Change> {
+        history.press(modifier.label().to_string(), Some(modifier))
+    }
+
+    fn shown(history: &History) -> Vec<String> {
+        history.entries().iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn repeats_are_counted_after_three() {
+        let mut history = History::default();
+
+        for _ in 0..3 {
+            press(&mut history, "A");
+        }
+        assert_eq!(shown(&history), ["A A A"]);
+
+        assert_eq!(press(&mut history, "A"), Some(Change::Updated));
+        assert_eq!(shown(&history), ["A...x4"]);
+
+        assert_eq!(press(&mut history, "B"), Some(Change::Pushed));
+        assert_eq!(history.entries().len(), 2);
+    }
+
+    #[test]
+    fn held_modifiers_become_a_chord() {
+        let mut history = History::default();
+
+        assert_eq!(hold(&mut history, Modifier::Control), Some(Change::Pushed));
+        assert_eq!(history.last().unwrap().kind, EntryKind::Modifier);
+
+        // auto repeat of the held modifier
+        assert_eq!(hold(&mut history, Modifier::Control), None);
+
+        assert_eq!(press(&mut history, "C"), Some(Change::Updated));
+
+        let last = history.last().unwrap();
+        assert_eq!(last.kind, EntryKind::Chord);
+        assert_eq!(last.modifiers, [Modifier::Control]);
+        assert_eq!(last.key, "C");
+        assert_eq!(history.entries().len(), 1);
+
+        // the same chord again while holding control
+        assert_eq!(press(&mut history, "C"), Some(Change::Updated));
+        assert_eq!(history.last().unwrap().repeat, 2);
+
+        history.release(Some(Modifier::Control));
+        press(&mut history, "C");
+        assert_eq!(history.last().unwrap().kind, EntryKind::Key);
+    }
+
+    #[test]
+    fn a_second_modifier_joins_the_first() {
+        let mut history = History::default();
+
+        hold(&mut history, Modifier::Control);
+        hold(&mut history, Modifier::Shift);
+        press(&mut history, "T");
+
+        assert_eq!(history.entries().len(), 1);
+        assert_eq!(
+            history.last().unwrap().modifiers,
+            [Modifier::Control, Modifier::Shift]
+        );
+    }
+
+    #[test]
+    fn backspace_erases_only_when_enabled() {
+        let mut history = History::new(true);
+
+        press(&mut history, "A");
+        press(&mut history, "B");
+        press(&mut history, "B");
+
+        assert_eq!(press(&mut history, BACK_SPACE), Some(Change::Updated));
+        assert_eq!(shown(&history), ["A", "B"]);
+
+        assert_eq!(press(&mut history, BACK_SPACE), Some(Change::Erased));
+        assert_eq!(shown(&history), ["A"]);
+
+        let mut history = History::default();
+
+        press(&mut history, "A");
+        press(&mut history, BACK_SPACE);
+        assert_eq!(shown(&history), ["A", BACK_SPACE]);
+    }
+
+    #[test]
+    fn commands_replace_the_pending_modifiers() {
+        let mut history = History::default();
+
+        hold(&mut history, Modifier::Shift);
+        assert_eq!(history.push_command("ZZ".to_string()), Change::Updated);
+
+        assert_eq!(history.entries().len(), 1);
+        assert_eq!(history.last().unwrap().kind, EntryKind::Command);
+        assert!(history.last().unwrap().is_shortcut());
+    }
+
+    #[test]
+    fn extending_a_command_replaces_it() {
+        let mut history = History::default();
+
+        history.push_command("C-x".to_string());
+        assert_eq!(
+            history.extend_command("C-x C-s".to_string()),
+            Change::Updated
+        );
+        assert_eq!(shown(&history), ["C-x C-s"]);
+
+        // only the last of the repeats is extended
+        history.push_command("C-x".to_string());
+        history.push_command("C-x".to_string());
+        assert_eq!(history.extend_command("C-x b".to_string()), Change::Pushed);
+        assert_eq!(shown(&history), ["C-x C-s", "C-x", "C-x b"]);
+    }
+
+    #[test]
+    fn shift_alone_is_not_a_shortcut() {
+        let mut history = History::default();
+
+        hold(&mut history, Modifier::Shift);
+        press(&mut history, "A");
+        assert!(!history.last().unwrap().is_shortcut());
+        assert!(!Mode::Shortcuts.shows(history.last().unwrap()));
+
+        hold(&mut history, Modifier::Alt);
+        press(&mut history, "A");
+        assert!(history.last().unwrap().is_shortcut());
+    }
+
+    #[test]
+    fn old_lines_are_dropped() {
+        let mut history = History::default();
+
+        for key in ["A", "B", "C"] {
+            press(&mut history, key);
+            history.break_line();
+        }
+
+        assert!(history.keep_lines(2, Mode::All));
+        assert_eq!(shown(&history), ["B", "C"]);
+
+        assert!(!history.keep_lines(2, Mode::All));
+    }
+
+    #[test]
+    fn hidden_lines_are_not_counted() {
+        let mut history = History::default();
+
+        hold(&mut history, Modifier::Control);
+        press(&mut history, "S");
+        history.release(Some(Modifier::Control));
+        history.break_line();
+
+        for key in ["A", "B"] {
+            press(&mut history, key);
+            history.break_line();
+        }
+
+        // the typed lines aren't shown, so the chord's line is one of the two kept
+        assert!(!history.keep_lines(2, Mode::Shortcuts));
+        assert_eq!(history.entries().len(), 3);
+        assert!(history.entries()[0].is_shortcut());
+    }
+}
@@ -0,0 +1,185 @@
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use serde::Deserialize;
+
+use crate::keys::{Keys, Modifier};
+
+/// A global shortcut like "Ctrl+Alt+C", checked against every captured key
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Hotkey {
+    pub modifiers: BTreeSet<Modifier>,
+    pub key: rdev::Key,
+}
+
+impl Hotkey {
+    pub fn matches(&self, held: &BTreeSet<Modifier>, key: &rdev::Key) -> bool {
+        self.key == *key && self.modifiers == *held
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = String;
+
+    fn from_str(hotkey: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<_> = hotkey.split('+').map(str::trim).collect();
+
+        let key = parts
+            .pop()
+            .filter(|key| !key.is_empty())
+            .ok_or_else(|| format!("\"{hotkey}\" has no key"))?;
+
+        let modifiers = parts
+            .into_iter()
+            .map(|modifier| match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => Ok(Modifier::Control),
+                "shift" => Ok(Modifier::Shift),
+                "alt" => Ok(Modifier::Alt),
+                "super" | "win" | "meta" | "cmd" => Ok(Modifier::Meta),
+                _ => Err(format!("unknown modifier \"{modifier}\" in \"{hotkey}\"")),
+            })
+            .collect::<Result<_, _>>()?;
+
+        let key = parse_key(key).ok_or_else(|| format!("unknown key \"{key}\" in \"{hotkey}\""))?;
+
+        if key.modifier().is_some() {
+            return Err(format!("\"{hotkey}\" ends with a modifier"));
+        }
+
+        Ok(Hotkey { modifiers, key })
+    }
+}
+
+impl TryFrom<String> for Hotkey {
+    type Error = String;
+
+    fn try_from(hotkey: String) -> Result<Self, Self::Error> {
+        hotkey.parse()
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for modifier in &self.modifiers {
+            let name = match modifier {
+                Modifier::Control => "Ctrl",
+                Modifier::Shift => "Shift",
+                Modifier::Alt => "Alt",
+                Modifier::Meta => "Super",
+            };
+
+            write!(f, "{name}+")?;
+        }
+
+        match self.key {
+            rdev::Key::Unknown(code) => write!(f, "{code}"),
+            key => {
+                let name = format!("{key:?}");
+                let name = name
+                    .strip_prefix("Key")
+                    .or_else(|| name.strip_prefix("Num").filter(|digit| digit.len() == 1))
+                    .unwrap_or(&name);
+
+                write!(f, "{name}")
+            }
+        }
+    }
+}
+
+/// Takes rdev's key names ("F12", "KeyA", "Escape"), and letters, digits, punctuation and a few common names
+pub fn parse_key(name: &str) -> Option<rdev::Key> {
+    let mut chars = name.chars();
+
+    let name = match (chars.next(), chars.next()) {
+        (Some(char), None) if char.is_ascii_alphabetic() => {
+            format!("Key{}", char.to_ascii_uppercase())
+        }
+        (Some(char), None) if char.is_ascii_digit() => format!("Num{char}"),
+        _ => match name.to_lowercase().as_str() {
+            "esc" => "Escape".to_string(),
+            "enter" => "Return".to_string(),
+            "del" => "Delete".to_string(),
+            "left" => "LeftArrow".to_string(),
+            "right" => "RightArrow".to_string(),
+            "up" => "UpArrow".to_string(),
+            "down" => "DownArrow".to_string(),
+            "pageup" => "PageUp".to_string(),
+            "pagedown" => "PageDown".to_string(),
+            "capslock" => "CapsLock".to_string(),
+            "printscreen" | "print" => "PrintScreen".to_string(),
+            "-" => "Minus".to_string(),
+            "=" => "Equal".to_string(),
+            "[" => "LeftBracket".to_string(),
+            "]" => "RightBracket".to_string(),
+            ";" => "SemiColon".to_string(),
+            "'" => "Quote".to_string(),
+            "," => "Comma".to_string(),
+            "." => "Dot".to_string(),
+            "/" => "Slash".to_string(),
+            "\\" => "BackSlash".to_string(),
+            "`" => "BackQuote".to_string(),
+            _ => {
+                // "space" and "f12" are fine too
+                let mut chars = name.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+        },
+    };
+
+    serde_json::from_value(serde_json::Value::String(name)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hotkeys_read_back() {
+        for hotkey in [
+            "Ctrl+Alt+C",
+            "Super+F12",
+            "Ctrl+Shift+1",
+            "Escape",
+            "Alt+LeftArrow",
+        ] {
+            let parsed: Hotkey = hotkey.parse().unwrap();
+
+            assert_eq!(parsed.to_string(), hotkey);
+            assert_eq!(parsed.to_string().parse::<Hotkey>(), Ok(parsed));
+        }
+    }
+
+    #[test]
+    fn names_are_loose() {
+        let hotkey: Hotkey = "control + win + esc".parse().unwrap();
+
+        assert_eq!(hotkey.to_string(), "Ctrl+Super+Escape");
+        assert_eq!(
+            "Shift+Cmd+,".parse::<Hotkey>().unwrap().to_string(),
+            "Shift+Super+Comma"
+        );
+    }
+
+    #[test]
+    fn bad_hotkeys_are_refused() {
+        for hotkey in ["", "Ctrl+", "Ctrl+Hyper+C", "Ctrl+Nope", "Ctrl+ShiftLeft"] {
+            assert!(hotkey.parse::<Hotkey>().is_err(), "{hotkey}");
+        }
+    }
+
+    #[test]
+    fn keys_are_parsed() {
+        assert_eq!(parse_key("a"), Some(rdev::Key::KeyA));
+        assert_eq!(parse_key("7"), Some(rdev::Key::Num7));
+        assert_eq!(parse_key("KeyZ"), Some(rdev::Key::KeyZ));
+        assert_eq!(parse_key("f5"), Some(rdev::Key::F5));
+        assert_eq!(parse_key("space"), Some(rdev::Key::Space));
+        assert_eq!(parse_key("PageUp"), Some(rdev::Key::PageUp));
+        assert_eq!(parse_key("\\"), Some(rdev::Key::BackSlash));
+        assert_eq!(parse_key("nope"), None);
+        assert_eq!(parse_key(""), None);
+    }
+}
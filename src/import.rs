@@ -0,0 +1,563 @@
+use std::{collections::BTreeMap, error::Error, path::Path};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::hotkey::Hotkey;
+
+/// Where a keymap comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// VS Code's keybindings.json
+    Vscode,
+    /// A JetBrains keymap .xml
+    Jetbrains,
+    /// The bindsym lines of an i3 or sway config
+    I3,
+}
+
+impl Format {
+    /// Guesses the format from the file name, configs without an extension are i3's
+    pub fn detect(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Format::Vscode,
+            Some("xml") => Format::Jetbrains,
+            _ => Format::I3,
+        }
+    }
+}
+
+/// Chords by their name like "Ctrl+Shift+P", and what they do
+#[derive(Debug, Default)]
+pub struct Keymap {
+    pub descriptions: BTreeMap<String, String>,
+    /// Bindings that couldn't be annotated, like sequences or unknown keys
+    pub skipped: Vec<String>,
+}
+
+/// Reads a keymap and prints it as annotations, or adds it to the `output` file
+pub fn run(
+    file: &Path,
+    format: Option<Format>,
+    app: Option<&str>,
+    output: Option<&Path>,
+) -> Result<(), Box<dyn Error>> {
+    let source = std::fs::read_to_string(file)?;
+    let keymap = Keymap::parse(&source, format.unwrap_or_else(|| Format::detect(file)))?;
+
+    for skipped in &keymap.skipped {
+        eprintln!("Skipped {skipped}");
+    }
+
+    match output {
+        Some(output) => {
+            keymap.write(output, app)?;
+            eprintln!(
+                "Added {} chords to {}",
+                keymap.descriptions.len(),
+                output.display()
+            );
+        }
+        None => print!("{}", keymap.to_toml(app)),
+    }
+
+    Ok(())
+}
+
+impl Keymap {
+    pub fn parse(source: &str, format: Format) -> Result<Self, String> {
+        let mut keymap = Self::default();
+
+        match format {
+            Format::Vscode => keymap.parse_vscode(source)?,
+            Format::Jetbrains => keymap.parse_jetbrains(source),
+            Format::I3 => keymap.parse_i3(source),
+        }
+
+        Ok(keymap)
+    }
+
+    /// Adds a binding, later ones replace earlier ones like they do in the editors
+    fn add(&mut self, chord: Result<Hotkey, String>, description: String) {
+        match chord {
+            Ok(hotkey) => {
+                self.descriptions.insert(hotkey.to_string(), description);
+            }
+            Err(e) => self.skipped.push(e),
+        }
+    }
+
+    fn parse_vscode(&mut self, source: &str) -> Result<(), String> {
+        #[derive(Deserialize)]
+        struct Binding {
+            key: String,
+            command: String,
+        }
+
+        let bindings: Vec<Binding> =
+            serde_json::from_str(&strip_comments(source)).map_err(|e| e.to_string())?;
+
+        for binding in bindings {
+            // "-command" removes a default binding
+            if binding.command.starts_with('-') || binding.command.is_empty() {
+                continue;
+            }
+
+            // "ctrl+k ctrl+s" is a sequence of two chords
+            let chord = if binding.key.trim().contains(' ') {
+                Err(format!("\"{}\" is a sequence", binding.key))
+            } else {
+                binding.key.parse()
+            };
+
+            let name = binding.command.rsplit('.').next().unwrap_or_default();
+            self.add(chord, words(name));
+        }
+
+        Ok(())
+    }
+
+    fn parse_jetbrains(&mut self, source: &str) {
+        let mut action = None;
+
+        for (name, attributes) in tags(source) {
+            match name {
+                "action" => action = attribute(attributes, "id"),
+                "keyboard-shortcut" => {
+                    let Some(id) = &action else {
+                        continue;
+                    };
+                    let Some(keystroke) = attribute(attributes, "first-keystroke") else {
+                        continue;
+                    };
+
+                    let chord = if attribute(attributes, "second-keystroke").is_some() {
+                        Err(format!("\"{keystroke}\" is the start of a sequence"))
+                    } else {
+                        jetbrains_chord(&keystroke)
+                    };
+
+                    self.add(chord, words(id.trim_start_matches('$')));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn parse_i3(&mut self, source: &str) {
+        let mut variables = Vec::new();
+        // bindings in `mode "resize" { ... }` blocks only work in that mode
+        let mut depth = 0;
+
+        for line in source.lines().map(str::trim) {
+            if line.starts_with('}') {
+                depth -= 1;
+            }
+
+            if line.ends_with('{') {
+                depth += 1;
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+
+            match words.next() {
+                Some("set") => {
+                    if let (Some(name), Some(value)) = (words.next(), words.next()) {
+                        variables.push((name.to_string(), value.to_string()));
+                        // longer names first, so $mod doesn't eat into $mod2
+                        variables.sort_by_key(|(name, _)| std::cmp::Reverse(name.len()));
+                    }
+                }
+                Some("bindsym") if depth == 0 => {
+                    // options like --release come before the keys
+                    let Some(keys) = words.find(|word| !word.starts_with("--")) else {
+                        continue;
+                    };
+
+                    let command = words.collect::<Vec<_>>().join(" ");
+
+                    if command.is_empty() {
+                        continue;
+                    }
+
+                    let keys = variables
+                        .iter()
+                        .fold(keys.to_string(), |keys, (name, value)| {
+                            keys.replace(name, value)
+                        });
+
+                    self.add(i3_chord(&keys), command);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The keymap as the `[global]` table of an annotation file, or `[apps.<app>]`
+    pub fn to_toml(&self, app: Option<&str>) -> String {
+        let mut document = toml_edit::DocumentMut::new();
+        self.merge_into(&mut document, app);
+
+        document.to_string()
+    }
+
+    /// Adds the keymap to an annotation file, keeping what's already in it
+    pub fn write(&self, path: &Path, app: Option<&str>) -> Result<(), Box<dyn Error>> {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut document = source.parse::<toml_edit::DocumentMut>()?;
+        self.merge_into(&mut document, app);
+
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(path, document.to_string())?;
+
+        Ok(())
+    }
+
+    fn merge_into(&self, document: &mut toml_edit::DocumentMut, app: Option<&str>) {
+        let table = match app {
+            Some(app) => {
+                let apps = document
+                    .entry("apps")
+                    .or_insert_with(toml_edit::table)
+                    .as_table_mut();
+
+                // only the app tables are shown, not an empty [apps]
+                if let Some(apps) = apps {
+                    apps.set_implicit(true);
+                }
+
+                &mut document["apps"][app]
+            }
+            None => document.entry("global").or_insert_with(toml_edit::table),
+        };
+
+        if table.is_none() {
+            *table = toml_edit::table();
+        }
+
+        for (chord, description) in &self.descriptions {
+            table[chord.as_str()] = toml_edit::value(description.as_str());
+        }
+    }
+}
+
+/// Drops the `//` and `/* */` comments and trailing commas VS Code allows in its JSON
+fn strip_comments(source: &str) -> String {
+    let chars: Vec<char> = source.chars().collect();
+    let mut json = String::with_capacity(source.len());
+    let mut in_string = false;
+    // a comma waits for what comes after the comments following it
+    let mut comma = false;
+    let mut index = 0;
+
+    while index < chars.len() {
+        let char = chars[index];
+        let next = chars.get(index + 1).copied();
+        index += 1;
+
+        if in_string {
+            json.push(char);
+
+            match char {
+                '\\' => {
+                    json.extend(next);
+                    index += 1;
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+
+            continue;
+        }
+
+        match (char, next) {
+            ('/', Some('/')) => {
+                while index < chars.len() && chars[index] != '\n' {
+                    index += 1;
+                }
+            }
+            ('/', Some('*')) => {
+                index += 1;
+
+                while index < chars.len() && !(chars[index - 1] == '*' && chars[index] == '/') {
+                    index += 1;
+                }
+
+                index += 1;
+            }
+            (',', _) => comma = true,
+            _ => {
+                if comma && !char.is_whitespace() {
+                    if !matches!(char, ']' | '}') {
+                        json.push(',');
+                    }
+
+                    comma = false;
+                }
+
+                in_string = char == '"';
+                json.push(char);
+            }
+        }
+    }
+
+    json
+}
+
+/// Splits a command or action name like "showCommands" or "ReformatCode" into "Show Commands"
+fn words(name: &str) -> String {
+    let mut words = String::new();
+    let mut previous: Option<char> = None;
+
+    for char in name.chars() {
+        if matches!(char, '_' | '-' | '.') {
+            words.push(' ');
+        } else {
+            let starts_word = char.is_uppercase()
+                && previous
+                    .is_some_and(|previous| previous.is_lowercase() || previous.is_ascii_digit());
+
+            if starts_word {
+                words.push(' ');
+            }
+
+            if words.trim().is_empty() {
+                words.extend(char.to_uppercase());
+            } else {
+                words.push(char);
+            }
+        }
+
+        previous = Some(char);
+    }
+
+    words.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The tags in an XML document, by their name and the text of their attributes
+fn tags(source: &str) -> impl Iterator<Item = (&str, &str)> {
+    source.split('<').skip(1).filter_map(|tag| {
+        let tag = tag.split('>').next()?.trim_end_matches('/');
+
+        if tag.starts_with(['/', '?', '!']) {
+            return None;
+        }
+
+        Some(tag.split_once(char::is_whitespace).unwrap_or((tag, "")))
+    })
+}
+
+/// The unescaped value of the `name="..."` attribute
+fn attribute(attributes: &str, name: &str) -> Option<String> {
+    let mut rest = attributes;
+
+    while let Some((key, value)) = rest.split_once('=') {
+        let value = value.trim_start();
+        let quote = value.chars().next()?;
+        let (value, after) = value[1..].split_once(quote)?;
+
+        if key.trim() == name {
+            return Some(
+                value
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&apos;", "'")
+                    .replace("&amp;", "&"),
+            );
+        }
+
+        rest = after;
+    }
+
+    None
+}
+
+/// A Java keystroke like "shift ctrl alt L" or "ctrl OPEN_BRACKET"
+fn jetbrains_chord(keystroke: &str) -> Result<Hotkey, String> {
+    let mut parts: Vec<_> = keystroke.split_whitespace().collect();
+    let key = parts.pop().unwrap_or_default();
+
+    let key = match key.to_lowercase().replace('_', "").as_str() {
+        "backspace" => "Backspace".to_string(),
+        "openbracket" => "[".to_string(),
+        "closebracket" => "]".to_string(),
+        "period" => ".".to_string(),
+        "equals" => "=".to_string(),
+        "backquote" => "`".to_string(),
+        "backslash" => "\\".to_string(),
+        "semicolon" => ";".to_string(),
+        "quote" => "'".to_string(),
+        "subtract" => "KpMinus".to_string(),
+        "add" => "KpPlus".to_string(),
+        "multiply" => "KpMultiply".to_string(),
+        "divide" => "KpDivide".to_string(),
+        key => key.to_string(),
+    };
+
+    parts.push(&key);
+    parts.join("+").parse()
+}
+
+/// An i3 or sway chord like "Mod4+Shift+q" or "Mod1+bracketleft", with X key names
+fn i3_chord(keys: &str) -> Result<Hotkey, String> {
+    let mut parts: Vec<_> = keys.split('+').collect();
+    let key = parts.pop().unwrap_or_default();
+
+    let modifiers = parts.into_iter().map(|modifier| match modifier {
+        "Mod1" => "Alt",
+        "Mod4" => "Super",
+        "Control" => "Ctrl",
+        modifier => modifier,
+    });
+
+    let key = match key {
+        "Return" => "Return",
+        "BackSpace" => "Backspace",
+        "Prior" => "PageUp",
+        "Next" => "PageDown",
+        "Print" => "PrintScreen",
+        "minus" => "-",
+        "equal" => "=",
+        "bracketleft" => "[",
+        "bracketright" => "]",
+        "semicolon" => ";",
+        "apostrophe" => "'",
+        "grave" => "`",
+        "backslash" => "\\",
+        "comma" => ",",
+        "period" => ".",
+        "slash" => "/",
+        key => key,
+    };
+
+    modifiers.chain([key]).collect::<Vec<_>>().join("+").parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The name a chord is stored under
+    fn chord(name: &str) -> String {
+        name.parse::<Hotkey>().unwrap().to_string()
+    }
+
+    fn description<'a>(keymap: &'a Keymap, name: &str) -> Option<&'a str> {
+        keymap.descriptions.get(&chord(name)).map(String::as_str)
+    }
+
+    #[test]
+    fn comments_and_trailing_commas_are_stripped() {
+        let source = r#"// keybindings.json
+            [
+                /* the palette */
+                { "key": "ctrl+shift+p", "command": "a//b", },
+                { "key": "ctrl+k", "command": "c/*d*/\"e", }, // last
+            ]"#;
+
+        let json: serde_json::Value = serde_json::from_str(&strip_comments(source)).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!([
+                { "key": "ctrl+shift+p", "command": "a//b" },
+                { "key": "ctrl+k", "command": "c/*d*/\"e" },
+            ])
+        );
+    }
+
+    #[test]
+    fn vscode_bindings_are_read() {
+        let source = r#"[
+            { "key": "ctrl+shift+p", "command": "workbench.action.showCommands" },
+            { "key": "ctrl+k ctrl+s", "command": "workbench.action.openGlobalKeybindings" },
+            { "key": "ctrl+b", "command": "-workbench.action.toggleSidebarVisibility" },
+        ]"#;
+
+        let keymap = Keymap::parse(source, Format::Vscode).unwrap();
+
+        assert_eq!(description(&keymap, "Ctrl+Shift+P"), Some("Show Commands"));
+        assert_eq!(keymap.descriptions.len(), 1);
+        assert_eq!(keymap.skipped.len(), 1);
+    }
+
+    #[test]
+    fn xml_tags_and_attributes_are_read() {
+        let source = r#"<?xml version="1.0"?>
+            <keymap name="Mine">
+              <action id="$Copy">
+                <keyboard-shortcut first-keystroke="ctrl C" />
+              </action>
+              <!-- a comment -->
+            </keymap>"#;
+
+        let tags: Vec<_> = tags(source).map(|(name, _)| name).collect();
+        assert_eq!(tags, ["keymap", "action", "keyboard-shortcut"]);
+
+        let attributes = r#"id='Find &amp; Replace' text="say &quot;hi&quot;""#;
+        assert_eq!(
+            attribute(attributes, "id").as_deref(),
+            Some("Find & Replace")
+        );
+        assert_eq!(attribute(attributes, "text").as_deref(), Some("say \"hi\""));
+        assert_eq!(attribute(attributes, "missing"), None);
+    }
+
+    #[test]
+    fn jetbrains_shortcuts_are_read() {
+        let source = r#"<keymap>
+              <action id="ReformatCode">
+                <keyboard-shortcut first-keystroke="shift ctrl alt L" />
+              </action>
+              <action id="GotoAction">
+                <keyboard-shortcut first-keystroke="ctrl K" second-keystroke="ctrl A" />
+              </action>
+            </keymap>"#;
+
+        let keymap = Keymap::parse(source, Format::Jetbrains).unwrap();
+
+        assert_eq!(
+            description(&keymap, "Shift+Ctrl+Alt+L"),
+            Some("Reformat Code")
+        );
+        assert_eq!(keymap.descriptions.len(), 1);
+        assert_eq!(keymap.skipped.len(), 1);
+    }
+
+    #[test]
+    fn i3_variables_are_substituted() {
+        let source = "set $mod Mod4\n\
+            set $mod2 Mod1\n\
+            bindsym $mod+Return exec terminal\n\
+            bindsym --release $mod2+q kill\n";
+
+        let keymap = Keymap::parse(source, Format::I3).unwrap();
+
+        assert_eq!(description(&keymap, "Super+Return"), Some("exec terminal"));
+        assert_eq!(description(&keymap, "Alt+Q"), Some("kill"));
+    }
+
+    #[test]
+    fn i3_mode_bindings_are_skipped() {
+        let source = "mode \"resize\" {\n\
+                bindsym h resize shrink width\n\
+                bindsym Return mode \"default\"\n\
+            }\n\
+            bindsym Mod4+r mode \"resize\"\n";
+
+        let keymap = Keymap::parse(source, Format::I3).unwrap();
+
+        assert_eq!(description(&keymap, "Super+R"), Some("mode \"resize\""));
+        assert_eq!(keymap.descriptions.len(), 1);
+    }
+}
@@ -0,0 +1,456 @@
+use iced::{alignment, Point};
+use iced_native::layout::{self, Layout};
+use iced_native::renderer::{self, Quad};
+use iced_native::text::Text;
+use iced_native::widget::Widget;
+use iced_native::{text, Background, Color, Element, Length, Rectangle, Size, Vector};
+
+use crate::{
+    history::{EntryKind, KeyEntry},
+    labels::Labels,
+    theme::Theme,
+};
+
+/// The counter is drawn smaller than the key it belongs to
+const COUNTER_SCALE: f32 = 0.6;
+
+/// One key-cap, a repeated key is shown as a counter on a single cap
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyCap {
+    pub label: String,
+    pub kind: EntryKind,
+    pub repeat: Option<u32>,
+}
+
+impl KeyCap {
+    /// Keys pressed more than 3 times in a row get a counter,
+    /// otherwise every press gets its own cap
+    pub fn from_entries<'a>(
+        entries: impl IntoIterator<Item = &'a KeyEntry>,
+        labels: &Labels,
+    ) -> Vec<Self> {
+        entries
+            .into_iter()
+            .flat_map(|entry| {
+                let (caps, repeat) = if entry.repeat > 3 {
+                    (1, Some(entry.repeat))
+                } else {
+                    (entry.repeat as usize, None)
+                };
+
+                std::iter::repeat_n(
+                    KeyCap {
+                        label: entry.label(labels),
+                        kind: entry.kind,
+                        repeat,
+                    },
+                    caps,
+                )
+            })
+            .collect()
+    }
+
+    pub fn counter(&self) -> Option<String> {
+        self.repeat.map(|repeat| format!("x{repeat}"))
+    }
+}
+
+/// Colors of one kind of cap
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CapStyle {
+    pub background: Color,
+    pub border_color: Color,
+    pub text_color: Color,
+}
+
+/// How the caps look, sizes are relative to the font size
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub key: CapStyle,
+    pub modifier: CapStyle,
+    pub chord: CapStyle,
+    pub counter: CapStyle,
+    pub border_radius: f32,
+    pub border_width: f32,
+    pub padding: f32,
+    pub spacing: f32,
+    pub shadow_offset: Vector,
+    pub shadow_color: Color,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Theme::default().caps
+    }
+}
+
+impl CapStyle {
+    fn faded(self, opacity: f32) -> Self {
+        Self {
+            background: fade(self.background, opacity),
+            border_color: fade(self.border_color, opacity),
+            text_color: fade(self.text_color, opacity),
+        }
+    }
+}
+
+fn fade(color: Color, opacity: f32) -> Color {
+    Color {
+        a: color.a * opacity,
+        ..color
+    }
+}
+
+impl Style {
+    /// The same style, more transparent
+    pub fn faded(self, opacity: f32) -> Self {
+        Self {
+            key: self.key.faded(opacity),
+            modifier: self.modifier.faded(opacity),
+            chord: self.chord.faded(opacity),
+            counter: self.counter.faded(opacity),
+            shadow_color: fade(self.shadow_color, opacity),
+            ..self
+        }
+    }
+
+    fn cap(&self, kind: EntryKind) -> &CapStyle {
+        match kind {
+            EntryKind::Key => &self.key,
+            EntryKind::Modifier => &self.modifier,
+            // commands are shortcuts too
+            EntryKind::Chord | EntryKind::Command => &self.chord,
+        }
+    }
+
+    /// Height of a cap with its shadow
+    pub fn height(&self, font_size: u32) -> u32 {
+        let size = font_size as f32;
+
+        (size + size * self.padding * 2.0 + size * self.shadow_offset.y).ceil() as u32
+    }
+}
+
+/// Picks the font of a label
+pub type FontFor<'a, Font> = Box<dyn Fn(&str) -> Font + 'a>;
+
+/// Draws every entry as its own rounded key-cap
+#[allow(missing_debug_implementations)]
+pub struct KeyCaps<'a, Renderer: text::Renderer> {
+    caps: Vec<KeyCap>,
+    truncated: bool,
+    size: Option<u16>,
+    font: Renderer::Font,
+    font_for: Option<FontFor<'a, Renderer::Font>>,
+    style: Style,
+    height: Length,
+}
+
+impl<'a, Renderer: text::Renderer> KeyCaps<'a, Renderer> {
+    pub fn new(caps: Vec<KeyCap>) -> Self {
+        KeyCaps {
+            caps,
+            truncated: false,
+            size: None,
+            font: Default::default(),
+            font_for: None,
+            style: Style::default(),
+            height: Length::Shrink,
+        }
+    }
+
+    /// Shows "..." in front of the caps, for when older keys were dropped
+    pub fn truncated(mut self, truncated: bool) -> Self {
+        self.truncated = truncated;
+        self
+    }
+
+    /// Sets the font size of the labels
+    pub fn size(mut self, size: u16) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn font(mut self, font: impl Into<Renderer::Font>) -> Self {
+        self.font = font.into();
+        self
+    }
+
+    /// Picks the font of each label, e.g. one that has all of its glyphs
+    pub fn font_for<F>(mut self, font_for: F) -> Self
+    where
+        F: 'a + Fn(&str) -> Renderer::Font,
+    {
+        self.font_for = Some(Box::new(font_for));
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets the height of the [`KeyCaps`], the caps are centered in it
+    pub fn height(mut self, height: Length) -> Self {
+        self.height = height;
+        self
+    }
+
+    fn font_of(&self, label: &str) -> Renderer::Font {
+        match &self.font_for {
+            Some(font_for) => font_for(label),
+            None => self.font.clone(),
+        }
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for KeyCaps<'a, Renderer>
+where
+    Renderer: text::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn height(&self) -> Length {
+        self.height
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(Length::Shrink).height(self.height);
+
+        let size = f32::from(self.size.unwrap_or(renderer.default_size()));
+        let padding = size * self.style.padding;
+        let spacing = size * self.style.spacing;
+        let cap_height = size + padding * 2.0;
+
+        let measure =
+            |text: &str, size: f32| renderer.measure_width(text, size as u16, self.font_of(text));
+
+        let mut children = Vec::new();
+        let mut x = 0.0;
+
+        if self.truncated {
+            let width = measure("...", size);
+            children.push(layout::Node::new(Size::new(width, cap_height)));
+            x += width + spacing;
+        }
+
+        for width in cap_widths(&self.caps, size, &self.style, measure) {
+            let mut node = layout::Node::new(Size::new(width, cap_height));
+            node.move_to(Point::new(x, 0.0));
+
+            x += width + spacing;
+            children.push(node);
+        }
+
+        let width = (x - spacing).max(0.0);
+        let shadow = size * self.style.shadow_offset.y.max(0.0);
+
+        let size = limits.resolve(Size::new(width, cap_height + shadow));
+
+        // center the caps vertically
+        let top = ((size.height - cap_height - shadow) / 2.0).max(0.0);
+        for child in &mut children {
+            let position = child.bounds().position();
+            child.move_to(Point::new(position.x, top));
+        }
+
+        layout::Node::with_children(size, children)
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let size = f32::from(self.size.unwrap_or(renderer.default_size()));
+
+        let mut children = layout.children();
+
+        if self.truncated {
+            if let Some(layout) = children.next() {
+                let bounds = layout.bounds();
+
+                renderer.fill_text(Text {
+                    content: "...",
+                    size,
+                    bounds: Rectangle {
+                        y: bounds.center_y(),
+                        ..bounds
+                    },
+                    color: self.style.key.text_color,
+                    font: self.font_of("..."),
+                    horizontal_alignment: alignment::Horizontal::Left,
+                    vertical_alignment: alignment::Vertical::Center,
+                });
+            }
+        }
+
+        for (cap, layout) in self.caps.iter().zip(children) {
+            draw(
+                renderer,
+                layout.bounds(),
+                cap,
+                size,
+                self.font_of(&cap.label),
+                cap.counter()
+                    .map(|counter| self.font_of(&counter))
+                    .unwrap_or_else(|| self.font.clone()),
+                &self.style,
+            );
+        }
+    }
+}
+
+/// The width of each cap, `measure` gives the width of a text at a font size
+pub fn cap_widths(
+    caps: &[KeyCap],
+    size: f32,
+    style: &Style,
+    measure: impl Fn(&str, f32) -> f32,
+) -> Vec<f32> {
+    let padding = size * style.padding;
+
+    caps.iter()
+        .map(|cap| {
+            let counter = cap.counter().map_or(0.0, |counter| {
+                padding + counter_width(&counter, size, &measure)
+            });
+
+            measure(&cap.label, size) + counter + padding * 2.0
+        })
+        .collect()
+}
+
+/// The width of all the caps next to each other
+pub fn strip_width(
+    caps: &[KeyCap],
+    truncated: bool,
+    size: f32,
+    style: &Style,
+    measure: impl Fn(&str, f32) -> f32,
+) -> f32 {
+    let spacing = size * style.spacing;
+
+    let truncated = if truncated {
+        measure("...", size) + spacing
+    } else {
+        0.0
+    };
+
+    let caps = cap_widths(caps, size, style, measure);
+    let spaces = caps.len().saturating_sub(1) as f32 * spacing;
+
+    truncated + caps.iter().sum::<f32>() + spaces
+}
+
+fn counter_width(counter: &str, size: f32, measure: impl Fn(&str, f32) -> f32) -> f32 {
+    let counter_size = size * COUNTER_SCALE;
+
+    measure(counter, counter_size) + counter_size * 0.6
+}
+
+impl<'a, Message, Renderer> From<KeyCaps<'a, Renderer>> for Element<'a, Message, Renderer>
+where
+    Renderer: 'a + text::Renderer,
+{
+    fn from(key_caps: KeyCaps<'a, Renderer>) -> Self {
+        Self::new(key_caps)
+    }
+}
+
+/// Draws one cap: its shadow, the cap itself, the label and the counter
+pub fn draw<Renderer>(
+    renderer: &mut Renderer,
+    bounds: Rectangle,
+    cap: &KeyCap,
+    size: f32,
+    font: Renderer::Font,
+    counter_font: Renderer::Font,
+    style: &Style,
+) where
+    Renderer: text::Renderer,
+{
+    let cap_style = style.cap(cap.kind);
+    let padding = size * style.padding;
+    let border_radius = size * style.border_radius;
+
+    renderer.fill_quad(
+        Quad {
+            bounds: bounds + style.shadow_offset * size,
+            border_radius,
+            border_width: 0.0,
+            border_color: Color::TRANSPARENT,
+        },
+        Background::Color(style.shadow_color),
+    );
+
+    renderer.fill_quad(
+        Quad {
+            bounds,
+            border_radius,
+            border_width: style.border_width,
+            border_color: cap_style.border_color,
+        },
+        Background::Color(cap_style.background),
+    );
+
+    renderer.fill_text(Text {
+        content: &cap.label,
+        size,
+        bounds: Rectangle {
+            x: bounds.x + padding,
+            y: bounds.center_y(),
+            ..bounds
+        },
+        color: cap_style.text_color,
+        font,
+        horizontal_alignment: alignment::Horizontal::Left,
+        vertical_alignment: alignment::Vertical::Center,
+    });
+
+    let Some(counter) = cap.counter() else {
+        return;
+    };
+
+    let counter_size = size * COUNTER_SCALE;
+    let width = counter_width(&counter, size, |text, size| {
+        renderer.measure_width(text, size as u16, counter_font.clone())
+    });
+    let height = counter_size * 1.3;
+
+    let badge = Rectangle {
+        x: bounds.x + bounds.width - padding - width,
+        y: bounds.center_y() - height / 2.0,
+        width,
+        height,
+    };
+
+    renderer.fill_quad(
+        Quad {
+            bounds: badge,
+            border_radius: height / 2.0,
+            border_width: style.border_width,
+            border_color: style.counter.border_color,
+        },
+        Background::Color(style.counter.background),
+    );
+
+    renderer.fill_text(Text {
+        content: &counter,
+        size: counter_size,
+        bounds: Rectangle {
+            x: badge.center_x(),
+            y: badge.center_y(),
+            ..badge
+        },
+        color: style.counter.text_color,
+        font: counter_font,
+        horizontal_alignment: alignment::Horizontal::Center,
+        vertical_alignment: alignment::Vertical::Center,
+    });
+}
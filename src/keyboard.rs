@@ -0,0 +1,437 @@
+use std::path::Path;
+
+use iced::Point;
+use iced_native::layout::{self, Layout};
+use iced_native::renderer;
+use iced_native::widget::Widget;
+use iced_native::{text, Element, Length, Rectangle, Size};
+use serde::Deserialize;
+
+use crate::{
+    heatmap::Heatmap,
+    history::EntryKind,
+    hotkey,
+    key_caps::{self, FontFor, KeyCap, Style},
+    keys::{rdev_to_key, Keys},
+};
+
+/// Built-in layouts, any other name is read as the path of a layout file
+pub const BUILT_IN: [(&str, &str); 3] = [
+    ("ansi", include_str!("../data/layouts/ansi.toml")),
+    ("iso", include_str!("../data/layouts/iso.toml")),
+    ("60", include_str!("../data/layouts/60.toml")),
+];
+
+/// Space between keys, relative to the key size
+const KEY_GAP: f32 = 0.1;
+/// Label size, relative to the key size
+pub const LABEL_SCALE: f32 = 0.35;
+/// Keys that aren't pressed are drawn faintly
+const IDLE_OPACITY: f32 = 0.35;
+
+/// The `[keyboard]` section
+#[derive(Debug, Clone, Deserialize)]
+pub struct KeyboardConfig {
+    pub enabled: Option<bool>,
+    /// "ansi", "iso", "60" or the path of a layout file
+    pub layout: Option<String>,
+    /// Size of a one unit key in pixels
+    pub key_size: Option<u16>,
+    /// Also show the pressed keys as text under the keyboard
+    pub strip: Option<bool>,
+    /// Color the keys by how often they were pressed
+    pub heatmap: Option<bool>,
+}
+
+impl Default for KeyboardConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Some(false),
+            layout: Some("ansi".to_string()),
+            key_size: Some(40),
+            strip: Some(true),
+            heatmap: Some(false),
+        }
+    }
+}
+
+impl KeyboardConfig {
+    pub fn key_size(&self) -> u16 {
+        self.key_size
+            .unwrap_or_else(|| KeyboardConfig::default().key_size.unwrap())
+    }
+}
+
+#[derive(Deserialize)]
+struct LayoutFile {
+    rows: Vec<Vec<String>>,
+}
+
+/// A key of the board, positions and sizes are in key units
+#[derive(Debug, Clone)]
+pub struct BoardKey {
+    pub key: rdev::Key,
+    pub label: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl BoardKey {
+    /// Where the key is drawn on a board with keys of `key_size` pixels
+    pub fn bounds(&self, origin: Point, key_size: u16) -> Rectangle {
+        let unit = f32::from(key_size);
+        let gap = unit * KEY_GAP;
+
+        Rectangle {
+            x: origin.x + self.x * unit,
+            y: origin.y + self.y * unit,
+            width: self.width * unit - gap,
+            height: self.height * unit - gap,
+        }
+    }
+}
+
+/// A keyboard layout and the keys held on it
+#[derive(Debug, Clone, Default)]
+pub struct Board {
+    keys: Vec<BoardKey>,
+    pressed: Vec<rdev::Key>,
+    width: f32,
+    height: f32,
+}
+
+impl Board {
+    /// Loads a built-in layout by its name, or a layout file
+    pub fn load(layout: &str) -> Result<Self, String> {
+        match BUILT_IN.iter().find(|(name, _)| *name == layout) {
+            Some((_, source)) => Self::parse(source),
+            None => {
+                let source = std::fs::read_to_string(Path::new(layout))
+                    .map_err(|e| format!("Could not read keyboard layout {layout}: {e}"))?;
+
+                Self::parse(&source).map_err(|e| format!("Keyboard layout {layout}: {e}"))
+            }
+        }
+    }
+
+    /// Reads rows of "name", "name:width" or "name:width:height", an empty name is a gap
+    fn parse(source: &str) -> Result<Self, String> {
+        let file: LayoutFile = toml::from_str(source).map_err(|e| e.to_string())?;
+
+        let mut board = Board::default();
+
+        for (row, specs) in file.rows.iter().enumerate() {
+            let y = row as f32;
+            let mut x = 0.0;
+
+            for spec in specs {
+                let mut parts = spec.split(':');
+
+                let name = parts.next().unwrap_or_default().trim();
+
+                let mut unit = |default: f32| match parts.next() {
+                    Some(unit) => unit
+                        .trim()
+                        .parse::<f32>()
+                        .map_err(|_| format!("\"{spec}\" has a bad size")),
+                    None => Ok(default),
+                };
+
+                let width = unit(1.0)?;
+                let height = unit(1.0)?;
+
+                if !name.is_empty() {
+                    let key =
+                        hotkey::parse_key(name).ok_or_else(|| format!("unknown key \"{name}\""))?;
+
+                    board.keys.push(BoardKey {
+                        key,
+                        label: rdev_to_key(&key),
+                        x,
+                        y,
+                        width,
+                        height,
+                    });
+
+                    board.height = board.height.max(y + height);
+                }
+
+                x += width;
+                board.width = board.width.max(x);
+            }
+        }
+
+        Ok(board)
+    }
+
+    /// Lights up a key, `name` is the text it typed, so its label follows the keyboard layout
+    pub fn press(&mut self, key: rdev::Key, name: Option<&str>) {
+        if !self.pressed.contains(&key) {
+            self.pressed.push(key);
+        }
+
+        let mut chars = name.unwrap_or_default().chars();
+
+        // only plain keys type their own label, not shifted or control characters
+        let shifted = self.pressed.iter().any(|key| key.modifier().is_some());
+
+        if let (Some(char), None, false) = (chars.next(), chars.next(), shifted) {
+            if !char.is_control() && !char.is_whitespace() {
+                for board_key in self
+                    .keys
+                    .iter_mut()
+                    .filter(|board_key| board_key.key == key)
+                {
+                    board_key.label = char.to_uppercase().collect();
+                }
+            }
+        }
+    }
+
+    pub fn release(&mut self, key: rdev::Key) {
+        self.pressed.retain(|pressed| *pressed != key);
+    }
+
+    /// Keeps the keys held on the board this one replaces
+    pub fn keep_pressed(&mut self, old: &Board) {
+        self.pressed = old.pressed.clone();
+    }
+
+    pub fn release_all(&mut self) {
+        self.pressed.clear();
+    }
+
+    pub fn keys(&self) -> &[BoardKey] {
+        &self.keys
+    }
+
+    pub fn width(&self, key_size: u16) -> u32 {
+        (self.width * f32::from(key_size)).ceil() as u32
+    }
+
+    pub fn height(&self, key_size: u16) -> u32 {
+        (self.height * f32::from(key_size)).ceil() as u32
+    }
+
+    fn is_pressed(&self, key: &rdev::Key) -> bool {
+        self.pressed.contains(key)
+    }
+
+    /// How a key is drawn, modifiers and keys pressed with them look like in the strip
+    fn kind(&self, key: &rdev::Key) -> EntryKind {
+        if key.modifier().is_some() {
+            EntryKind::Modifier
+        } else if self.is_pressed(key) && self.pressed.iter().any(|key| key.modifier().is_some()) {
+            EntryKind::Chord
+        } else {
+            EntryKind::Key
+        }
+    }
+}
+
+/// Draws a [`Board`] with its pressed keys lit up
+#[allow(missing_debug_implementations)]
+pub struct Keyboard<'a, Renderer: text::Renderer> {
+    board: &'a Board,
+    heatmap: Option<&'a Heatmap>,
+    key_size: u16,
+    font: Renderer::Font,
+    font_for: Option<FontFor<'a, Renderer::Font>>,
+    style: Style,
+}
+
+impl<'a, Renderer: text::Renderer> Keyboard<'a, Renderer> {
+    pub fn new(board: &'a Board) -> Self {
+        Keyboard {
+            board,
+            heatmap: None,
+            key_size: KeyboardConfig::default().key_size.unwrap(),
+            font: Default::default(),
+            font_for: None,
+            style: Style::default(),
+        }
+    }
+
+    /// Sets the size of a one unit key in pixels
+    pub fn key_size(mut self, key_size: u16) -> Self {
+        self.key_size = key_size;
+        self
+    }
+
+    /// Colors the keys that aren't pressed by how often they were
+    pub fn heatmap(mut self, heatmap: Option<&'a Heatmap>) -> Self {
+        self.heatmap = heatmap;
+        self
+    }
+
+    /// Picks the font of each label, e.g. one that has all of its glyphs
+    pub fn font_for<F>(mut self, font_for: F) -> Self
+    where
+        F: 'a + Fn(&str) -> Renderer::Font,
+    {
+        self.font_for = Some(Box::new(font_for));
+        self
+    }
+
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    fn font_of(&self, label: &str) -> Renderer::Font {
+        match &self.font_for {
+            Some(font_for) => font_for(label),
+            None => self.font.clone(),
+        }
+    }
+
+    /// Pressed keys are lit up, the rest are faint or colored by the heatmap
+    fn style_of(&self, key: &rdev::Key, idle: &Style) -> Style {
+        if self.board.is_pressed(key) {
+            return self.style;
+        }
+
+        match self.heatmap.filter(|heatmap| heatmap.count(key) > 0) {
+            Some(heatmap) => {
+                let mut style = self.style;
+                style.key.background = heatmap.color(key, style.key.background);
+                style.modifier.background = heatmap.color(key, style.modifier.background);
+                style
+            }
+            None => *idle,
+        }
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Keyboard<'a, Renderer>
+where
+    Renderer: text::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let size = Size::new(
+            self.board.width(self.key_size) as f32,
+            self.board.height(self.key_size) as f32,
+        );
+
+        layout::Node::new(limits.resolve(size))
+    }
+
+    fn draw(
+        &self,
+        renderer: &mut Renderer,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let origin = layout.bounds().position();
+        let unit = f32::from(self.key_size);
+        let idle = self.style.faded(IDLE_OPACITY);
+
+        for board_key in &self.board.keys {
+            let bounds = board_key.bounds(origin, self.key_size);
+
+            let font = self.font_of(&board_key.label);
+
+            // long labels like "PageDown" are shrunk to fit their key
+            let mut size = unit * LABEL_SCALE;
+            let padding = size * self.style.padding;
+            let width = renderer.measure_width(&board_key.label, size as u16, font.clone());
+
+            if width > bounds.width - padding * 2.0 {
+                size *= ((bounds.width - padding * 2.0) / width).max(0.0);
+            }
+
+            let cap = KeyCap {
+                label: board_key.label.clone(),
+                kind: self.board.kind(&board_key.key),
+                repeat: None,
+            };
+
+            key_caps::draw(
+                renderer,
+                bounds,
+                &cap,
+                size,
+                font.clone(),
+                font,
+                &self.style_of(&board_key.key, &idle),
+            );
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<Keyboard<'a, Renderer>> for Element<'a, Message, Renderer>
+where
+    Renderer: 'a + text::Renderer,
+{
+    fn from(keyboard: Keyboard<'a, Renderer>) -> Self {
+        Self::new(keyboard)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_layouts_load() {
+        for (name, _) in BUILT_IN {
+            let board = Board::load(name).unwrap_or_else(|e| panic!("{name}: {e}"));
+
+            for key in [rdev::Key::Space, rdev::Key::Return, rdev::Key::KeyQ] {
+                assert!(
+                    board.keys().iter().any(|board_key| board_key.key == key),
+                    "{name} has no {key:?}"
+                );
+            }
+            assert!(board.width(40) > 0 && board.height(40) > 0);
+        }
+    }
+
+    #[test]
+    fn sizes_and_gaps_move_the_keys() {
+        let board =
+            Board::parse(r#"rows = [["Escape", ":0.5", "F1:1.5"], ["Tab:1.5:2"]]"#).unwrap();
+
+        let keys = board.keys();
+        assert_eq!(keys.len(), 3);
+        assert_eq!(
+            (keys[1].key, keys[1].x, keys[1].width),
+            (rdev::Key::F1, 1.5, 1.5)
+        );
+        assert_eq!((keys[2].y, keys[2].height), (1.0, 2.0));
+        assert_eq!((board.width, board.height), (3.0, 3.0));
+    }
+
+    #[test]
+    fn unknown_keys_are_refused() {
+        let e = Board::parse(r#"rows = [["Escape", "Nope"]]"#).unwrap_err();
+
+        assert_eq!(e, "unknown key \"Nope\"");
+    }
+
+    #[test]
+    fn malformed_rows_are_refused() {
+        for source in [
+            r#"rows = [["Tab:wide"]]"#,
+            r#"rows = [["Tab:1:"]]"#,
+            r#"rows = ["Tab"]"#,
+            r#"keys = [["Tab"]]"#,
+        ] {
+            assert!(Board::parse(source).is_err(), "{source}");
+        }
+
+        assert!(Board::load("/no/such/layout.toml").is_err());
+    }
+}
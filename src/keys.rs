@@ -1,40 +1,212 @@
+use std::{
+    panic::{self, AssertUnwindSafe},
+    time::{Duration, Instant},
+};
+
 use iced::{
-    futures::{channel::mpsc, FutureExt, SinkExt, StreamExt, TryFutureExt},
+    futures::{channel::mpsc, StreamExt},
+    keyboard::KeyCode,
     Subscription,
 };
 
-#[cfg(not(target_os = "linux"))]
-use iced::keyboard::KeyCode;
-
 use iced_native::subscription;
 use rdev::listen;
+use serde::{Deserialize, Serialize};
 
 pub trait Keys {
-    
+    /// The modifier this key acts as, if any.
+    fn modifier(&self) -> Option<Modifier>;
+
+    /// Whether the key types a character, like letters, digits, punctuation and space.
+    fn is_printable(&self) -> bool;
 }
 
-#[cfg(not(target_os = "linux"))]
 impl Keys for KeyCode {
-    
+    fn modifier(&self) -> Option<Modifier> {
+        match self {
+            KeyCode::LControl | KeyCode::RControl => Some(Modifier::Control),
+            KeyCode::LShift | KeyCode::RShift => Some(Modifier::Shift),
+            KeyCode::LAlt | KeyCode::RAlt => Some(Modifier::Alt),
+            KeyCode::LWin | KeyCode::RWin => Some(Modifier::Meta),
+            _ => None,
+        }
+    }
+
+    fn is_printable(&self) -> bool {
+        matches!(
+            self,
+            KeyCode::Key1
+                | KeyCode::Key2
+                | KeyCode::Key3
+                | KeyCode::Key4
+                | KeyCode::Key5
+                | KeyCode::Key6
+                | KeyCode::Key7
+                | KeyCode::Key8
+                | KeyCode::Key9
+                | KeyCode::Key0
+                | KeyCode::A
+                | KeyCode::B
+                | KeyCode::C
+                | KeyCode::D
+                | KeyCode::E
+                | KeyCode::F
+                | KeyCode::G
+                | KeyCode::H
+                | KeyCode::I
+                | KeyCode::J
+                | KeyCode::K
+                | KeyCode::L
+                | KeyCode::M
+                | KeyCode::N
+                | KeyCode::O
+                | KeyCode::P
+                | KeyCode::Q
+                | KeyCode::R
+                | KeyCode::S
+                | KeyCode::T
+                | KeyCode::U
+                | KeyCode::V
+                | KeyCode::W
+                | KeyCode::X
+                | KeyCode::Y
+                | KeyCode::Z
+                | KeyCode::Space
+                | KeyCode::Apostrophe
+                | KeyCode::Backslash
+                | KeyCode::Comma
+                | KeyCode::Equals
+                | KeyCode::Grave
+                | KeyCode::LBracket
+                | KeyCode::Minus
+                | KeyCode::Period
+                | KeyCode::RBracket
+                | KeyCode::Semicolon
+                | KeyCode::Slash
+        )
+    }
 }
 
 impl Keys for rdev::Key {
-    
+    fn modifier(&self) -> Option<Modifier> {
+        match self {
+            rdev::Key::ControlLeft | rdev::Key::ControlRight => Some(Modifier::Control),
+            rdev::Key::ShiftLeft | rdev::Key::ShiftRight => Some(Modifier::Shift),
+            rdev::Key::Alt | rdev::Key::AltGr => Some(Modifier::Alt),
+            rdev::Key::MetaLeft | rdev::Key::MetaRight => Some(Modifier::Meta),
+            _ => None,
+        }
+    }
+
+    fn is_printable(&self) -> bool {
+        matches!(
+            self,
+            rdev::Key::Num1
+                | rdev::Key::Num2
+                | rdev::Key::Num3
+                | rdev::Key::Num4
+                | rdev::Key::Num5
+                | rdev::Key::Num6
+                | rdev::Key::Num7
+                | rdev::Key::Num8
+                | rdev::Key::Num9
+                | rdev::Key::Num0
+                | rdev::Key::KeyA
+                | rdev::Key::KeyB
+                | rdev::Key::KeyC
+                | rdev::Key::KeyD
+                | rdev::Key::KeyE
+                | rdev::Key::KeyF
+                | rdev::Key::KeyG
+                | rdev::Key::KeyH
+                | rdev::Key::KeyI
+                | rdev::Key::KeyJ
+                | rdev::Key::KeyK
+                | rdev::Key::KeyL
+                | rdev::Key::KeyM
+                | rdev::Key::KeyN
+                | rdev::Key::KeyO
+                | rdev::Key::KeyP
+                | rdev::Key::KeyQ
+                | rdev::Key::KeyR
+                | rdev::Key::KeyS
+                | rdev::Key::KeyT
+                | rdev::Key::KeyU
+                | rdev::Key::KeyV
+                | rdev::Key::KeyW
+                | rdev::Key::KeyX
+                | rdev::Key::KeyY
+                | rdev::Key::KeyZ
+                | rdev::Key::Space
+                | rdev::Key::Quote
+                | rdev::Key::BackSlash
+                | rdev::Key::IntlBackslash
+                | rdev::Key::Comma
+                | rdev::Key::Equal
+                | rdev::Key::BackQuote
+                | rdev::Key::LeftBracket
+                | rdev::Key::Minus
+                | rdev::Key::Dot
+                | rdev::Key::RightBracket
+                | rdev::Key::SemiColon
+                | rdev::Key::Slash
+        )
+    }
+}
+
+/// Modifier keys, ordered the way they are shown in a chord.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Modifier {
+    Control,
+    Shift,
+    Alt,
+    Meta,
+}
+
+impl Modifier {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Modifier::Control => CONTROL,
+            Modifier::Shift => SHIFT,
+            Modifier::Alt => "Alt",
+            Modifier::Meta => "Win",
+        }
+    }
 }
 
 pub enum State {
     Starting,
-    Ready(mpsc::UnboundedReceiver<rdev::Event>),
+    Ready(mpsc::UnboundedReceiver<Event>),
 }
 
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Event {
     Ready,
     EventRecieved(rdev::Event),
+    /// Listening stopped, it is tried again after `retry_in`
+    Error {
+        message: String,
+        retry_in: Duration,
+    },
+}
+
+/// Where keys come from while listening fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fallback {
+    #[default]
+    None,
+    /// Keys pressed while the overlay window is focused
+    Window,
 }
 
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
 const TAB: &str = "";
-const ENTER: &str = "⏎";
+pub const ENTER: &str = "⏎";
 const SHIFT: &str = "וּ";
 const LEFT_ARROW: &str = "ﰯ";
 const RIGHT_ARROW: &str = "ﰲ";
@@ -54,22 +226,14 @@ pub fn bind() -> Subscription<Event> {
         |state| async move {
             match state {
                 State::Starting => {
-                    let (mut sender, receiver) = mpsc::unbounded();
-                    std::thread::spawn(move || {
-                        listen(move |event| {
-                            sender
-                                .send(event)
-                                .unwrap_or_else(|e| eprintln!("Could not send event {:?}", e))
-                                .now_or_never();
-                        })
-                        .expect("Could not listen");
-                    });
+                    let (sender, receiver) = mpsc::unbounded();
+                    std::thread::spawn(move || listen_with_retries(sender));
                     (Some(Event::Ready), State::Ready(receiver))
                 }
                 State::Ready(mut input) => {
                     let received = input.next().await;
                     match received {
-                        Some(key) => (Some(Event::EventRecieved(key)), State::Ready(input)),
+                        Some(event) => (Some(event), State::Ready(input)),
                         None => (None, State::Ready(input)),
                     }
                 }
@@ -78,7 +242,47 @@ pub fn bind() -> Subscription<Event> {
     )
 }
 
-#[cfg(not(target_os = "linux"))]
+/// Listens for keys until the app closes, reporting why it stopped
+/// and waiting longer after each failure in a row
+fn listen_with_retries(sender: mpsc::UnboundedSender<Event>) {
+    let mut retry_in = FIRST_RETRY_DELAY;
+
+    loop {
+        let started = Instant::now();
+
+        let events = sender.clone();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            listen(move |event| {
+                events
+                    .unbounded_send(Event::EventRecieved(event))
+                    .unwrap_or_else(|e| eprintln!("Could not send event {:?}", e));
+            })
+        }));
+
+        let message = match result {
+            Ok(Ok(())) => "stopped listening".to_string(),
+            Ok(Err(e)) => format!("could not listen: {e:?}"),
+            Err(_) => "the listener crashed".to_string(),
+        };
+
+        // it worked for a while, so this isn't a failure in a row
+        if started.elapsed() > MAX_RETRY_DELAY {
+            retry_in = FIRST_RETRY_DELAY;
+        }
+
+        // the app is closed
+        if sender
+            .unbounded_send(Event::Error { message, retry_in })
+            .is_err()
+        {
+            return;
+        }
+
+        std::thread::sleep(retry_in);
+        retry_in = (retry_in * 2).min(MAX_RETRY_DELAY);
+    }
+}
+
 pub fn iced_to_key(key_code: &KeyCode) -> String {
     match key_code {
         KeyCode::Key1 => "1".to_string(),
@@ -372,9 +576,8 @@ pub fn rdev_to_key(key_code: &rdev::Key) -> String {
 }
 
 fn unknown_to_key(unknown: &u32) -> String {
-    println!("{unknown}");
     match unknown {
-        171 => format!("怜"),
-        _ => format!("{unknown}")
+        171 => "怜".to_string(),
+        _ => format!("{unknown}"),
     }
 }
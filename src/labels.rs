@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use crate::{
+    hotkey::parse_key,
+    keys::{rdev_to_key, Modifier},
+};
+
+/// Labels shown instead of the built-in ones, like "Enter" for "⏎"
+#[derive(Debug, Clone, Default)]
+pub struct Labels {
+    /// By the built-in label
+    labels: HashMap<String, String>,
+}
+
+impl Labels {
+    /// Takes the labels by key names like "Return", "KeyA" or "Ctrl"
+    pub fn parse(labels: &HashMap<String, String>) -> Result<Self, String> {
+        let labels = labels
+            .iter()
+            .map(|(name, label)| {
+                let modifier = match name.to_lowercase().as_str() {
+                    "ctrl" | "control" => Some(Modifier::Control),
+                    "shift" => Some(Modifier::Shift),
+                    "alt" => Some(Modifier::Alt),
+                    "super" | "win" | "meta" | "cmd" => Some(Modifier::Meta),
+                    _ => None,
+                };
+
+                let built_in = match modifier {
+                    Some(modifier) => modifier.label().to_string(),
+                    None => rdev_to_key(
+                        &parse_key(name).ok_or_else(|| format!("unknown key \"{name}\""))?,
+                    ),
+                };
+
+                Ok((built_in, label.clone()))
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Self { labels })
+    }
+
+    /// The label shown for a built-in one
+    pub fn get<'a>(&'a self, label: &'a str) -> &'a str {
+        self.labels.get(label).map_or(label, String::as_str)
+    }
+}
@@ -1,13 +1,34 @@
-#![cfg_attr(not(debug_assertion), windows_subsystem = "windows")]
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use crate::keys::rdev_to_key;
+use crate::keys::{iced_to_key, rdev_to_key, Fallback, Keys, Modifier, ENTER};
 
-#[cfg(not(target_os = "linux"))]
-use crate::keys::iced_to_key;
-
-#[cfg(not(target_os = "linux"))]
 use iced::keyboard::Event;
 
+use annotations::Annotations;
+use apps::{Apps, HiddenApps};
+use clap::Parser;
+use cli::Cli;
+use config::{
+    config_path, data_dir, read_config, read_config_or_base, save_position, Config, FontPaths,
+    Loaded,
+};
+use fonts::Fonts;
+use heatmap::Heatmap;
+use history::{History, KeyEntry, Mode};
+use hotkey::Hotkey;
+use key_caps::{KeyCap, KeyCaps};
+use keyboard::{Board, Keyboard};
+use labels::Labels;
+use position::{Monitor, PositionConfig};
+use remote::RemoteConfig;
+use report::{ReportConfig, Session};
+use sequence::{Sequence, Sequences};
+use server::{PageStyle, Server, ServerConfig};
+use speed::Speed;
+use stack::{Layout, Line, StackConfig};
+use theme::Theme;
+use vim::Vim;
+
 use iced::{
     container::{Style, StyleSheet},
     executor, mouse, time,
@@ -16,63 +37,133 @@ use iced::{
     Application, Background, Color, Command, Element, Font, Settings, Subscription,
 };
 
-use iced_native::{subscription, widget::Text, window as native_window};
-use keys::BACK_SPACE;
-use serde::Deserialize;
+use iced_native::{
+    subscription,
+    widget::{Column, Row, Space, Text},
+    window as native_window,
+};
 use std::{
+    collections::BTreeSet,
     io::Cursor,
-    time::{Duration, Instant},
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
 };
-use toml::from_str;
 
+mod annotations;
+mod apps;
+mod cli;
+mod config;
+#[cfg(unix)]
+mod control;
+#[cfg(target_os = "linux")]
+mod dbus;
+mod fonts;
+mod headless;
+mod heatmap;
+mod history;
+mod hotkey;
+mod import;
+mod key_caps;
+mod keyboard;
 mod keys;
-mod my_text;
-
-#[derive(Debug, Deserialize)]
-struct Config {
-    position: Option<PositionConfig>,
-    font_size: Option<u32>,
-    width: Option<u32>,
-    erase_on_backspace: Option<bool>,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            position: Some(PositionConfig::default()),
-            font_size: Some(30),
-            width: Some(500),
-            erase_on_backspace: Some(false),
-        }
-    }
-}
-
-#[derive(Debug, Deserialize)]
-struct PositionConfig {
-    x: i32,
-    y: i32,
-}
-
-impl Default for PositionConfig {
-    fn default() -> Self {
-        Self { x: 1000, y: 1000 }
-    }
-}
+mod labels;
+mod position;
+mod profiles;
+mod remote;
+mod report;
+mod sequence;
+mod server;
+mod signals;
+mod speed;
+mod stack;
+mod theme;
+mod vim;
+mod watch;
+#[cfg(target_os = "linux")]
+mod x11;
 
 #[derive(Default)]
 struct ScreenKey {
-    keys: String,
-    key_frequency: u32,
-    frequent_key: String,
+    history: History,
+    truncated: bool,
+    mode: Mode,
+    layout: Layout,
+    stack: StackConfig,
+    last_press: Option<Instant>,
+    /// The layout of the on-screen keyboard and the heatmap
+    board: Board,
+    /// The on-screen keyboard is shown
+    keyboard: bool,
+    key_size: u16,
+    heatmap: Heatmap,
+    /// The on-screen keyboard is colored by the heatmap
+    show_heatmap: bool,
+    /// Typing speed, shown in a badge after the keys if it's enabled
+    speed: Option<Speed>,
+    session: Session,
+    report: ReportConfig,
+    /// The app closes after this update
+    exiting: bool,
+    annotations: Annotations,
+    /// What the last pressed chord does, shown under the keys
+    annotation: Option<String>,
+    /// Shows Vim's commands instead of its keys while it's focused, if it's enabled
+    vim: Option<Vim>,
+    /// Groups chords into sequences like "C-x C-s", if it's enabled
+    sequences: Option<Sequences>,
+    /// The apps with their own options and annotations, and the focused one
+    apps: Apps,
+    labels: Labels,
+    /// The keys are shown as text too, always when there's no keyboard
+    strip: bool,
+    paused: bool,
+    /// text shown instead of the keys until the next key press
+    message: Option<String>,
+    /// When the message goes away by itself, if it's a toast
+    toast_until: Option<Instant>,
     max_width: u32,
-    width: u32,
     font_size: u32,
     is_grabbing: bool,
+    /// Where the window was moved since the right button was pressed
+    dragged_to: Option<(i32, i32)>,
     grab_location: (i32, i32),
     window_position: (i32, i32),
+    /// The size the window was last given, it's only resized when that changes
+    window_size: Option<(u32, u32)>,
+    position: PositionConfig,
+    monitors: Vec<Monitor>,
+    /// The window follows its anchor until it's dragged away
+    anchored: bool,
+    /// The config moved the window, it's placed again even if it isn't anchored
+    position_changed: bool,
     timer_state: TimerState,
     duration: Duration,
-    erase_on_backspace: bool,
+    server: Option<Server>,
+    #[cfg(target_os = "linux")]
+    dbus: Option<dbus::Service>,
+    remote: RemoteConfig,
+    /// why listening for keys stopped, until it works again
+    capture_error: Option<String>,
+    capture_fallback: Fallback,
+    theme: Theme,
+    fonts: Fonts,
+    font_paths: Option<FontPaths>,
+    /// What the window last wrote to the config file, so it isn't applied again
+    written_config: Option<String>,
+    /// Modifiers held right now, even while paused
+    held: BTreeSet<Modifier>,
+    /// Modifier presses counted once they turn out not to be part of the overlay's hotkeys
+    uncounted: Vec<(rdev::Key, SystemTime)>,
+    /// Mouse input passes through the window
+    click_through: bool,
+    /// Turns click-through off and on again, if it's enabled in the config
+    click_through_hotkey: Option<Hotkey>,
+    /// The profile in use
+    profile: Option<String>,
+    /// Profile names, in the order they're cycled through
+    profiles: Vec<String>,
+    /// Switches to the next profile, if there are any
+    profile_hotkey: Option<Hotkey>,
 }
 
 #[derive(Default)]
@@ -88,27 +179,44 @@ enum TimerState {
 pub enum Message {
     RdevEvents(keys::Event),
     IcedEvents(iced_native::Event),
-    InputChanged(String),
     Tick(Instant),
+    /// The config file was written
+    ConfigChanged,
+    /// Ctrl+C or SIGTERM
+    Exit,
+    #[cfg(unix)]
+    Control(control::Request),
+    #[cfg(target_os = "linux")]
+    DBus(dbus::Event),
 }
 
+const FONT_BYTES: &[u8] = include_bytes!("../fonts/Fura Code Bold Nerd Font Complete Mono.ttf");
+
 const FONT: Font = Font::External {
     name: "Nerd Font",
-    bytes: include_bytes!("../fonts/Fura Code Bold Nerd Font Complete Mono.ttf"),
+    bytes: FONT_BYTES,
 };
 
-struct ContainerStyles;
+/// Space between the keys and the window edges
+const PADDING: u16 = 10;
+
+/// Shown in front of the keys while they can't be captured
+const CAPTURE_ERROR_BADGE: &str = "⚠";
+const BADGE_SPACING: u16 = 10;
+
+/// How long a toast like the profile name is shown
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+
+struct ContainerStyles {
+    background: Color,
+    border_radius: f32,
+}
 
 impl StyleSheet for ContainerStyles {
     fn style(&self) -> Style {
         Style {
-            background: Some(Background::Color(Color {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-                a: 0.5,
-            })),
-            border_radius: 10.,
+            background: Some(Background::Color(self.background)),
+            border_radius: self.border_radius,
             ..Default::default()
         }
     }
@@ -117,46 +225,51 @@ impl StyleSheet for ContainerStyles {
 impl Application for ScreenKey {
     type Executor = executor::Default;
     type Message = Message;
-    type Flags = ();
+    type Flags = Config;
 
-    fn new(_flags: Self::Flags) -> (Self, iced::Command<Self::Message>) {
-        let config_dir = dirs::config_dir().expect("No Config Directory");
+    fn new(config: Self::Flags) -> (Self, iced::Command<Self::Message>) {
+        let remote = config.remote.clone().unwrap_or_default();
 
-        let config_file = config_dir.join("zr-alshasha/config.toml");
+        if remote.listen.is_some() && remote.secret.is_none() {
+            eprintln!("Remote keys need a secret, set it in the [remote] section");
+        }
 
-        let config_str = std::fs::read_to_string(config_file).unwrap_or_default();
+        let mut screen_key = Self {
+            timer_state: TimerState::Ticking {
+                last_tick: Instant::now(),
+            },
+            remote,
+            monitors: position::monitors(),
+            apps: Apps::connect(),
+            ..Default::default()
+        };
 
-        let config: Config = from_str(&config_str).unwrap_or_else(|e| {
-            eprintln!("{e}");
+        let (loaded, errors) = Loaded::new(&config, screen_key.font_paths.as_ref());
 
-            Config::default()
-        });
+        for error in &errors {
+            eprintln!("{error}");
+        }
 
-        let max_width = config
-            .width
-            .unwrap_or_else(|| Config::default().width.unwrap());
+        screen_key.apply_config(&config, loaded);
 
-        (
-            Self {
-                keys: "".to_string(),
-                key_frequency: 0,
-                frequent_key: "".to_string(),
-                max_width,
-                width: 0,
-                font_size: config
-                    .font_size
-                    .unwrap_or_else(|| Config::default().font_size.unwrap()),
-                is_grabbing: false,
-                grab_location: (0, 0),
-                window_position: (0, 0),
-                timer_state: TimerState::Ticking {
-                    last_tick: Instant::now(),
-                },
-                duration: Duration::default(),
-                erase_on_backspace: config.erase_on_backspace.unwrap_or_default(),
-            },
-            Command::none(),
-        )
+        // shown until the first key press, so a missing font is noticed
+        screen_key.message = (!errors.is_empty()).then(|| errors.join(", "));
+
+        let server_config = config.server.clone().unwrap_or_default();
+
+        if server_config.enabled.unwrap_or_default() {
+            let address = server_config
+                .address
+                .unwrap_or_else(|| ServerConfig::default().address.unwrap());
+
+            screen_key.server = Server::spawn(&address, screen_key.page_style())
+                .map_err(|e| eprintln!("Could not start the overlay server on {address}: {e}"))
+                .ok();
+        }
+
+        let command = screen_key.refresh();
+
+        (screen_key, command)
     }
 
     fn title(&self) -> String {
@@ -171,29 +284,153 @@ impl Application for ScreenKey {
                     println!("Ready to recieve!");
                 }
                 keys::Event::EventRecieved(rdev_event) => {
-                    if let rdev::EventType::KeyPress(key) = rdev_event.event_type {
-                        return self.add_key(&key, rdev_to_key);
+                    self.capture_error = None;
+
+                    match rdev_event.event_type {
+                        rdev::EventType::KeyPress(key) => {
+                            if let Some(modifier) = key.modifier() {
+                                self.held.insert(modifier);
+                            }
+
+                            // the overlay's own hotkeys aren't shown or counted
+                            if self
+                                .click_through_hotkey
+                                .as_ref()
+                                .is_some_and(|hotkey| hotkey.matches(&self.held, &key))
+                            {
+                                self.drop_hotkey_modifiers();
+                                return self.toggle_click_through();
+                            }
+
+                            if self
+                                .profile_hotkey
+                                .as_ref()
+                                .is_some_and(|hotkey| hotkey.matches(&self.held, &key))
+                            {
+                                self.drop_hotkey_modifiers();
+                                return self.cycle_profile();
+                            }
+
+                            self.update_focus();
+
+                            if !self.ignores_keys() {
+                                self.board.press(key, rdev_event.name.as_deref());
+
+                                if key.modifier().is_some() {
+                                    self.uncounted.push((key, rdev_event.time));
+                                } else {
+                                    self.count_modifiers();
+                                    self.count(key, rdev_event.time);
+                                    self.annotate(key);
+                                }
+                            }
+
+                            if !self.ignores_keys() && self.is_vim_focused() {
+                                if let Some(vim) = &mut self.vim {
+                                    let output =
+                                        vim.press(key, rdev_event.name.as_deref(), &self.held);
+
+                                    return self.add_vim_output(&key, output);
+                                }
+                            }
+
+                            if !self.ignores_keys()
+                                && key.modifier().is_none()
+                                && self.sequences.is_some()
+                            {
+                                let apps = &self.apps.classes;
+                                // not self.annotations(), the sequences are borrowed too
+                                let annotations = self
+                                    .apps
+                                    .focused()
+                                    .and_then(|app| app.annotations.as_ref())
+                                    .unwrap_or(&self.annotations);
+
+                                let sequence = self.sequences.as_mut().and_then(|sequences| {
+                                    let hotkey = Hotkey {
+                                        modifiers: self.held.clone(),
+                                        key,
+                                    };
+
+                                    sequences.press(hotkey, Instant::now(), |keys| {
+                                        annotations.continues(keys, apps)
+                                    })
+                                });
+
+                                if let Some(sequence) = sequence {
+                                    return self.add_sequence(sequence);
+                                }
+                            }
+
+                            if self.ignores_keys() {
+                                return Command::none();
+                            }
+
+                            return self.add_key(&key, rdev_to_key);
+                        }
+                        rdev::EventType::KeyRelease(key) => {
+                            // a modifier pressed alone
+                            self.count_modifiers();
+
+                            if let Some(modifier) = key.modifier() {
+                                self.held.remove(&modifier);
+                            }
+
+                            self.board.release(key);
+
+                            self.history.release(key.modifier());
+                        }
+                        rdev::EventType::ButtonPress(_) => {
+                            let ignored = self.ignores_keys();
+
+                            if let (Some(speed), false) = (&mut self.speed, ignored) {
+                                speed.click(rdev_event.time);
+                            }
+                        }
+                        _ => {}
                     }
                 }
+                keys::Event::Error { message, retry_in } => {
+                    eprintln!("{message}, trying again in {retry_in:?}");
+                    self.capture_error = Some(message);
+                }
             },
             Message::IcedEvents(event) => match event {
-                #[cfg(not(target_os = "linux"))]
                 iced_native::Event::Keyboard(Event::KeyPressed {
                     key_code,
                     modifiers: _,
-                }) => {
+                }) if self.uses_window_keys() => {
+                    if !self.paused {
+                        self.count_speed(&key_code, SystemTime::now());
+                    }
+
                     return self.add_key(&key_code, iced_to_key);
                 }
+                iced_native::Event::Keyboard(Event::KeyReleased {
+                    key_code,
+                    modifiers: _,
+                }) if self.uses_window_keys() => {
+                    self.history.release(key_code.modifier());
+                }
                 iced_native::Event::Mouse(mouse::Event::ButtonPressed(
                     iced::mouse::Button::Right,
                 )) => {
                     self.is_grabbing = true;
+                    self.dragged_to = None;
                     self.grab_location = (0, 0);
                 }
                 iced_native::Event::Mouse(mouse::Event::ButtonReleased(
                     iced::mouse::Button::Right,
                 )) => {
                     self.is_grabbing = false;
+
+                    // the window may not have reported its last move yet
+                    if let Some((x, y)) = self.dragged_to.take() {
+                        match save_position(x, y, self.profile.as_deref()) {
+                            Ok(written) => self.written_config = Some(written),
+                            Err(e) => eprintln!("Could not save the window position: {e}"),
+                        }
+                    }
                 }
                 iced_native::Event::Mouse(mouse::Event::CursorMoved { position }) => {
                     if self.grab_location == (0, 0) {
@@ -201,34 +438,70 @@ impl Application for ScreenKey {
                     }
 
                     if self.is_grabbing {
+                        self.anchored = false;
+
                         let x = position.x as i32 + self.window_position.0 - self.grab_location.0;
                         let y = position.y as i32 + self.window_position.1 - self.grab_location.1;
+                        self.dragged_to = Some((x, y));
                         return window::move_to(x, y);
                     }
                 }
                 iced_native::Event::Window(native_window::Event::Moved { x, y }) => {
                     self.window_position = (x, y);
                 }
+                iced_native::Event::Window(native_window::Event::CloseRequested) => {
+                    self.exit();
+                }
                 _ => {
                     // println!("{event:?}");
                 }
             },
-            Message::InputChanged(new_value) => {
-                self.keys = new_value;
+            #[cfg(unix)]
+            Message::Control(request) => {
+                return self.control(request);
+            }
+            #[cfg(target_os = "linux")]
+            Message::DBus(event) => match event {
+                dbus::Event::Ready(service) => {
+                    self.dbus = Some(service);
+                }
+                dbus::Event::Request(request) => {
+                    return self.control(request);
+                }
+            },
+            Message::ConfigChanged => {
+                return self.reload_config();
+            }
+            Message::Exit => {
+                self.exit();
             }
-            Message::Tick(now) => match &mut self.timer_state {
-                TimerState::Ticking { last_tick } => {
+            Message::Tick(now) => {
+                if self.toast_until.is_some_and(|until| now >= until) {
+                    self.toast_until = None;
+                    self.message = None;
+                    return self.refresh();
+                }
+
+                // the speeds go down while nothing is typed
+                let refresh = if self.speed.is_some() {
+                    self.refresh()
+                } else {
+                    Command::none()
+                };
+
+                if let TimerState::Ticking { last_tick } = &mut self.timer_state {
                     if self.duration.as_secs() > 3 {
                         #[cfg(debug_assertions)]
                         println!("{:?}", self.duration);
 
-                        return Command::none();
+                        return refresh;
                     }
                     self.duration += now - *last_tick;
                     *last_tick = now;
                 }
-                _ => {}
-            },
+
+                return refresh;
+            }
         }
         Command::none()
     }
@@ -237,35 +510,157 @@ impl Application for ScreenKey {
         Color::TRANSPARENT
     }
 
+    fn should_exit(&self) -> bool {
+        self.exiting
+    }
+
     fn subscription(&self) -> Subscription<Message> {
         let iced_events = subscription::events().map(Message::IcedEvents);
+
+        let remote_keys = match (&self.remote.listen, &self.remote.secret) {
+            (Some(address), Some(secret)) => {
+                remote::receive(address.clone(), secret.clone()).map(Message::RdevEvents)
+            }
+            _ => Subscription::none(),
+        };
+
+        // the remote machine's keys replace this one's unless asked otherwise
+        let local_keys = if self.remote.listen.is_none() || self.remote.local_keys == Some(true) {
+            keys::bind().map(Message::RdevEvents)
+        } else {
+            Subscription::none()
+        };
+
         Subscription::batch(vec![
-            keys::bind().map(Message::RdevEvents),
+            local_keys,
+            remote_keys,
             iced_events,
+            #[cfg(unix)]
+            control::listen().map(Message::Control),
+            #[cfg(target_os = "linux")]
+            dbus::listen().map(Message::DBus),
+            signals::listen().map(|_| Message::Exit),
+            watch::changes(config_path()).map(|_| Message::ConfigChanged),
             match self.timer_state {
                 TimerState::Ticking { .. } => {
                     time::every(Duration::from_secs(1)).map(Message::Tick)
                 }
                 TimerState::Idle => Subscription::none(),
             },
+            match self.toast_until {
+                Some(_) => time::every(Duration::from_millis(250)).map(Message::Tick),
+                None => Subscription::none(),
+            },
         ])
     }
 
     fn view(&mut self) -> Element<'_, Self::Message> {
-        container::Container::new(
-            Text::new(self.keys.clone())
+        let fonts = &self.fonts;
+
+        let keys: Element<'_, Self::Message> = match &self.message {
+            Some(message) => Text::new(message.clone())
                 .size(self.font_size as u16)
                 .height(iced::Length::Fill)
-                .color(Color::WHITE)
-                .font(FONT)
+                .color(self.theme.text)
+                .font(fonts.for_text(message))
                 .vertical_alignment(iced::alignment::Vertical::Center)
-                .horizontal_alignment(iced::alignment::Horizontal::Left),
-        )
-        .width(iced::Length::Fill)
-        .height(iced::Length::Fill)
-        .center_x()
-        .style(ContainerStyles)
-        .into()
+                .horizontal_alignment(iced::alignment::Horizontal::Left)
+                .into(),
+            None => {
+                let lines = self.lines().into_iter().map(|line| {
+                    KeyCaps::new(line.caps)
+                        .truncated(line.truncated)
+                        .style(self.theme.caps.faded(line.opacity))
+                        .size(line.size)
+                        .font_for(move |label| fonts.for_text(label))
+                        .into()
+                });
+
+                Column::with_children(lines.collect())
+                    .height(iced::Length::Fill)
+                    .align_items(iced::Alignment::Center)
+                    .into()
+            }
+        };
+
+        let speed_badge = self.speed_badge();
+
+        let strip: Element<'_, Self::Message> =
+            if self.capture_error.is_some() || speed_badge.is_some() {
+                let mut row = Row::new().spacing(BADGE_SPACING);
+
+                // a badge in front of the keys while they can't be captured
+                if self.capture_error.is_some() {
+                    row = row.push(
+                        Text::new(CAPTURE_ERROR_BADGE)
+                            .size(self.font_size as u16)
+                            .height(iced::Length::Fill)
+                            .color(Color::from_rgb(1.0, 0.3, 0.3))
+                            .font(fonts.for_text(CAPTURE_ERROR_BADGE))
+                            .vertical_alignment(iced::alignment::Vertical::Center),
+                    );
+                }
+
+                row = row.push(keys);
+
+                if let Some(badge) = speed_badge {
+                    row = row.push(
+                        Text::new(badge.clone())
+                            .size((self.font_size as f32 * speed::BADGE_SCALE) as u16)
+                            .height(iced::Length::Fill)
+                            .color(self.theme.text)
+                            .font(fonts.for_text(&badge))
+                            .vertical_alignment(iced::alignment::Vertical::Center),
+                    );
+                }
+
+                row.into()
+            } else {
+                keys
+            };
+
+        if self.is_empty() {
+            return Space::new(iced::Length::Fill, iced::Length::Fill).into();
+        }
+
+        let mut content = Column::new()
+            .height(iced::Length::Fill)
+            .spacing(PADDING)
+            .align_items(iced::Alignment::Center);
+
+        if self.keyboard {
+            content = content.push(
+                Keyboard::new(&self.board)
+                    .key_size(self.key_size)
+                    .heatmap(self.show_heatmap.then_some(&self.heatmap))
+                    .style(self.theme.caps)
+                    .font_for(move |label| fonts.for_text(label)),
+            );
+        }
+
+        if self.shows_strip() {
+            content = content.push(strip);
+
+            if let Some(annotation) = &self.annotation {
+                content = content.push(
+                    Text::new(annotation.clone())
+                        .size(self.annotation_size())
+                        .color(self.theme.text)
+                        .font(fonts.for_text(annotation)),
+                );
+            }
+        }
+
+        container::Container::new(content)
+            .width(iced::Length::Fill)
+            .height(iced::Length::Fill)
+            .padding([0, PADDING])
+            .center_x()
+            .style(ContainerStyles {
+                background: self.theme.background,
+                border_radius: self.theme.background_radius,
+            })
+            .into()
     }
 }
 
@@ -290,128 +685,886 @@ impl ScreenKey {
     where
         KS: keys::Keys,
     {
-        Self::erase_timer(&mut self.duration);
+        if self.paused {
+            return Command::none();
+        }
 
-        let coming_key = key_to_string(key);
+        self.show(|history| history.press(key_to_string(key), key.modifier()))
+    }
 
-        // if erase_on_backspace is specified as true in the config file
-        // erase the last key when pressing backspace
-        if self.erase_on_backspace {
-            if coming_key == BACK_SPACE {
-                let keys: Vec<&str> = self.keys.trim_end().rsplitn(2, ' ').collect();
-                if keys.len() == 1 {
-                    self.keys.clear();
-                } else {
-                    self.keys = format!("{} ", keys[keys.len() - 1].to_string());
-                }
-                self.key_frequency = 0;
-                self.frequent_key = "".to_string();
-                return Command::none();
+    /// Shows what Vim made of a key instead of the key itself
+    fn add_vim_output(&mut self, key: &rdev::Key, output: vim::Output) -> Command<Message> {
+        match output {
+            vim::Output::Key => {
+                self.show(|history| history.press(rdev_to_key(key), key.modifier()))
+            }
+            vim::Output::Hidden => match self.history.drop_pending() {
+                Some(_) => self.refresh(),
+                None => Command::none(),
+            },
+            vim::Output::Command { keys, description } => {
+                self.annotation = self.annotations().vim_line(&keys, description);
+
+                self.show(|history| Some(history.push_command(keys)))
             }
         }
+    }
 
-        // reset repeated key
-        if self.frequent_key != coming_key {
-            self.key_frequency = 0;
-            self.frequent_key = "".to_string();
+    /// Shows a chord sequence as one entry, in Emacs notation
+    fn add_sequence(&mut self, sequence: Sequence) -> Command<Message> {
+        let name = sequence.name();
+
+        self.annotation = self
+            .annotations()
+            .line(&name, &sequence.keys, &self.apps.classes);
+
+        self.show(|history| {
+            Some(if sequence.extended {
+                history.extend_command(name)
+            } else {
+                history.push_command(name)
+            })
+        })
+    }
+
+    /// Counts a captured key press in the heatmap, the session and the speed
+    fn count(&mut self, key: rdev::Key, time: SystemTime) {
+        self.heatmap.press(&key);
+        self.session.press(key, &self.held, time);
+        self.count_speed(&key, time);
+    }
+
+    /// Counts the modifiers pressed before, now that they're not a hotkey
+    fn count_modifiers(&mut self) {
+        for (key, time) in std::mem::take(&mut self.uncounted) {
+            self.count(key, time);
         }
-        
-        let frequent_key = format!("{}...x{} ", self.frequent_key, self.key_frequency);
+    }
 
-        self.key_frequency += 1;
+    /// The modifiers of one of the overlay's hotkeys are neither shown nor counted
+    fn drop_hotkey_modifiers(&mut self) {
+        self.uncounted.clear();
+        self.history.drop_pending();
+    }
 
-        self.frequent_key = coming_key.clone();
+    fn count_speed<KS: keys::Keys>(&mut self, key: &KS, time: SystemTime) {
+        if let Some(speed) = &mut self.speed {
+            let typed = key.is_printable() && !history::is_shortcut(&self.held);
 
-        let new_frequent_key = format!("{}...x{} ", self.frequent_key, self.key_frequency);
+            speed.press(time, typed);
+        }
+    }
 
-        // if key is pressed more than 3 types 
-        // replace the last 3 with <key>...x3
-        if self.key_frequency > 3 {
-            let repeated_key = format!(
-                "{} {} {} ",
-                self.frequent_key, self.frequent_key, self.frequent_key
-            );
+    /// Adds an entry with `press`, and updates the window for it
+    fn show(
+        &mut self,
+        press: impl FnOnce(&mut History) -> Option<history::Change>,
+    ) -> Command<Message> {
+        Self::erase_timer(&mut self.duration);
 
-            if self.keys.ends_with(repeated_key.as_str()) {
-                self.keys = format!(
-                    "{}{}",
-                    self.keys.trim_end_matches(repeated_key.as_str()),
-                    new_frequent_key
-                );
-            } else {
-                self.keys = format!(
-                    "{}{}",
-                    self.keys.trim_end_matches(frequent_key.as_str()),
-                    new_frequent_key
-                );
+        let stacked = self.layout == Layout::Stack;
+
+        // a pause starts a new burst of typing
+        if stacked
+            && self
+                .last_press
+                .is_some_and(|last_press| last_press.elapsed() > self.stack.pause())
+        {
+            self.history.break_line();
+        }
+
+        self.last_press = Some(Instant::now());
+
+        if press(&mut self.history).is_none() {
+            return Command::none();
+        }
+
+        if stacked
+            && self
+                .history
+                .last()
+                .is_some_and(|last| last.key == ENTER && last.modifiers.is_empty())
+        {
+            self.history.break_line();
+        }
+
+        self.message = None;
+        self.toast_until = None;
+
+        #[cfg(target_os = "linux")]
+        if let (Some(dbus), Some(entry)) = (&self.dbus, self.history.last()) {
+            if self.mode().shows(entry) {
+                dbus.key_displayed(&entry.label(self.labels()));
             }
-        } else {
-            self.keys = format!("{}{} ", self.keys, coming_key);
         }
 
-        self.width = self.keys.chars().filter(|char| char != &' ').count() as u32 * self.font_size;
+        self.refresh()
+    }
 
-        let coming_key_length = coming_key.chars().count();
+    /// Updates the shown keys after the history, mode or size changed
+    fn refresh(&mut self) -> Command<Message> {
+        match self.layout {
+            // limit shown keys to max width
+            Layout::Strip => {
+                while self.message.is_none()
+                    && self.history.entries().len() > 1
+                    && self.strip_width() > self.max_width
+                {
+                    self.history.pop_front();
+                    self.truncated = true;
+                }
+            }
+            Layout::Stack => {
+                let last_line = self.history.last().map(|last| last.line);
+                let last_line_len = self
+                    .history
+                    .entries()
+                    .iter()
+                    .filter(|entry| Some(entry.line) == last_line)
+                    .count();
 
-        // limit shown characters to max width
-        if self.width >= self.max_width {
-            self.keys = format!(
-                "...{}",
-                self.keys
-                    .chars()
-                    .skip(4 + coming_key_length)
-                    .collect::<String>()
+                // the newest key goes on its own line when it doesn't fit
+                if self.message.is_none()
+                    && last_line_len > 1
+                    && self.strip_width() > self.max_width
+                {
+                    self.history.wrap_last();
+                }
+
+                if self.history.keep_lines(self.stack.lines(), self.mode()) {
+                    self.truncated = true;
+                }
+            }
+        }
+
+        if let Some(server) = &self.server {
+            server.publish(
+                &self.visible_entries(),
+                self.labels(),
+                self.truncated,
+                self.message.as_deref(),
             );
         }
 
-        Command::single(iced_native::command::Action::Window(
+        let size = if self.is_empty() {
+            // iced can't hide the window, but it's transparent with nothing in it
+            (1, 1)
+        } else {
+            (self.content_width(), self.window_height())
+        };
+
+        if self.window_size == Some(size) {
+            return Command::none();
+        }
+
+        self.window_size = Some(size);
+
+        let resize = Command::single(iced_native::command::Action::Window(
             native_window::Action::Resize {
-                width: self.max_width,
-                height: self.font_size + 10,
+                width: size.0,
+                height: size.1,
             },
-        ))
+        ));
+
+        if !self.anchored && !std::mem::take(&mut self.position_changed) {
+            return resize;
+        }
+
+        // keep the anchored edges in place with the new size
+        let (x, y) = self.position.resolve(&self.monitors, size);
+
+        Command::batch([resize, window::move_to(x, y)])
+    }
+
+    fn visible_entries(&self) -> Vec<&KeyEntry> {
+        let entries = self.history.entries().iter();
+
+        entries.filter(|entry| self.mode().shows(entry)).collect()
+    }
+
+    fn caps(&self) -> Vec<KeyCap> {
+        KeyCap::from_entries(self.visible_entries(), self.labels())
+    }
+
+    /// The width of the window, the keyboard is never cut off
+    fn content_width(&self) -> u32 {
+        let strip = if self.shows_strip() {
+            let annotation = self.annotation.as_ref().map_or(0, |annotation| {
+                let width = self
+                    .fonts
+                    .measure(annotation, f32::from(self.annotation_size()));
+
+                width.ceil() as u32 + 2 * u32::from(PADDING) + 2
+            });
+
+            self.strip_width().max(annotation).min(self.max_width)
+        } else {
+            0
+        };
+
+        let keyboard = if self.keyboard {
+            self.board.width(self.key_size) + 2 * u32::from(PADDING)
+        } else {
+            0
+        };
+
+        strip.max(keyboard)
+    }
+
+    /// The width of the text strip, measured with the fonts it's drawn with
+    fn strip_width(&self) -> u32 {
+        let size = self.font_size as f32;
+        let measure = |text: &str, size: f32| self.fonts.measure(text, size);
+
+        let mut width = match &self.message {
+            Some(message) => measure(message, size),
+            None => self
+                .lines()
+                .iter()
+                .map(|line| {
+                    key_caps::strip_width(
+                        &line.caps,
+                        line.truncated,
+                        f32::from(line.size),
+                        &self.theme.caps,
+                        measure,
+                    )
+                })
+                .fold(0.0, f32::max),
+        };
+
+        if self.capture_error.is_some() {
+            width += measure(CAPTURE_ERROR_BADGE, size) + BADGE_SPACING as f32;
+        }
+
+        if let Some(badge) = self.speed_badge() {
+            width += measure(&badge, size * speed::BADGE_SCALE) + BADGE_SPACING as f32;
+        }
+
+        // the renderer may round differently
+        width.ceil() as u32 + 2 * u32::from(PADDING) + 2
+    }
+
+    /// Nothing to show, so the window is hidden
+    fn is_empty(&self) -> bool {
+        !self.keyboard && !self.shows_strip()
+    }
+
+    /// Messages and badges are shown even if the strip is turned off
+    fn shows_strip(&self) -> bool {
+        self.message.is_some()
+            || self.capture_error.is_some()
+            || self.speed.is_some()
+            || (self.strip && !self.visible_entries().is_empty())
+    }
+
+    /// "62 wpm 180 apm", if the speed is shown
+    fn speed_badge(&self) -> Option<String> {
+        let now = SystemTime::now();
+
+        self.speed
+            .as_ref()
+            .map(|speed| format!("{} wpm {} apm", speed.wpm(now), speed.apm(now)))
+    }
+
+    fn window_height(&self) -> u32 {
+        let keyboard = self.keyboard.then(|| self.board.height(self.key_size));
+
+        let strip = self.shows_strip().then(|| self.strip_height());
+
+        let annotation = self
+            .annotation
+            .as_ref()
+            .filter(|_| self.shows_strip())
+            .map(|_| u32::from(self.annotation_size()));
+
+        let parts: Vec<_> = [keyboard, strip, annotation]
+            .into_iter()
+            .flatten()
+            .collect();
+
+        // the parts are PADDING apart
+        parts.iter().sum::<u32>() + parts.len().saturating_sub(1) as u32 * u32::from(PADDING) + 10
     }
+
+    fn annotation_size(&self) -> u16 {
+        (self.font_size as f32 * annotations::SCALE) as u16
+    }
+
+    /// Shows what `key` does with the held modifiers, if it's in the annotations
+    fn annotate(&mut self, key: rdev::Key) {
+        let hotkey = Hotkey {
+            modifiers: self.held.clone(),
+            key,
+        };
+
+        self.annotation = self.annotations().line(
+            &hotkey.to_string(),
+            std::slice::from_ref(&hotkey),
+            &self.apps.classes,
+        );
+    }
+
+    fn is_vim_focused(&self) -> bool {
+        let Some(vim) = &self.vim else {
+            return false;
+        };
+
+        // without knowing the focused app, every key is meant for Vim
+        !self.apps.knows_focus() || vim.is_focused(&self.apps.classes, &self.apps.title)
+    }
+
+    /// Something shown depends on the focused app
+    fn uses_focus(&self) -> bool {
+        !self.apps.is_empty() || self.vim.is_some() || self.annotations.has_apps()
+    }
+
+    /// Finds the focused window and the options of its app, once for every key press
+    fn update_focus(&mut self) {
+        if self.uses_focus() {
+            self.apps.update();
+        }
+    }
+
+    /// The focused app's mode, or the one of the config
+    fn mode(&self) -> Mode {
+        self.apps
+            .focused()
+            .and_then(|app| app.mode)
+            .unwrap_or(self.mode)
+    }
+
+    fn labels(&self) -> &Labels {
+        self.apps
+            .focused()
+            .and_then(|app| app.labels.as_ref())
+            .unwrap_or(&self.labels)
+    }
+
+    fn annotations(&self) -> &Annotations {
+        self.apps
+            .focused()
+            .and_then(|app| app.annotations.as_ref())
+            .unwrap_or(&self.annotations)
+    }
+
+    /// Paused, or an app that hides its keys is focused
+    fn ignores_keys(&self) -> bool {
+        self.paused || self.apps.focused().is_some_and(|app| app.hide)
+    }
+
+    fn strip_height(&self) -> u32 {
+        let lines = self.lines();
+
+        if self.message.is_some() || lines.is_empty() {
+            return self.theme.caps.height(self.font_size);
+        }
+
+        lines
+            .iter()
+            .map(|line| self.theme.caps.height(line.size.into()))
+            .sum()
+    }
+
+    /// The lines of caps, the newest last
+    fn lines(&self) -> Vec<Line> {
+        if self.layout == Layout::Strip {
+            return vec![Line {
+                caps: self.caps(),
+                size: self.font_size as u16,
+                opacity: 1.0,
+                truncated: self.truncated,
+            }];
+        }
+
+        let mut lines: Vec<Vec<&KeyEntry>> = Vec::new();
+
+        for entry in self.visible_entries() {
+            match lines.last_mut() {
+                Some(line) if line[0].line == entry.line => line.push(entry),
+                _ => lines.push(vec![entry]),
+            }
+        }
+
+        let skipped = lines.len().saturating_sub(self.stack.lines());
+
+        let mut lines: Vec<Line> = lines
+            .into_iter()
+            .skip(skipped)
+            .rev()
+            .enumerate()
+            .map(|(age, entries)| {
+                let (scale, opacity) = self.stack.fade(age);
+
+                Line {
+                    caps: KeyCap::from_entries(entries, self.labels()),
+                    size: (self.font_size as f32 * scale) as u16,
+                    opacity,
+                    truncated: false,
+                }
+            })
+            .rev()
+            .collect();
+
+        // the keys were dropped from the oldest line
+        if let Some(oldest) = lines.first_mut() {
+            oldest.truncated = self.truncated;
+        }
+
+        lines
+    }
+
+    /// Applies the config unless something it points to has errors, then the current one stays
+    fn try_apply_config(&mut self, config: &Config) -> Result<(), String> {
+        let (loaded, errors) = Loaded::new(config, self.font_paths.as_ref());
+
+        if !errors.is_empty() {
+            return Err(errors.join(", "));
+        }
+
+        self.apply_config(config, loaded);
+
+        Ok(())
+    }
+
+    /// Applies the options that can change while the app is running
+    fn apply_config(&mut self, config: &Config, loaded: Loaded) {
+        // the window is resized and placed again with the new options
+        self.window_size = None;
+
+        self.max_width = config
+            .width
+            .unwrap_or_else(|| Config::default().width.unwrap());
+
+        self.font_size = config
+            .font_size
+            .unwrap_or_else(|| Config::default().font_size.unwrap());
+
+        self.mode = config.mode.unwrap_or_default();
+
+        self.layout = config.layout.unwrap_or_default();
+
+        self.stack = config.stack.clone().unwrap_or_default();
+
+        let keyboard = config.keyboard.clone().unwrap_or_default();
+
+        // keys held while reloading stay lit
+        let mut board = loaded.board;
+        board.keep_pressed(&self.board);
+        self.board = board;
+
+        if let Some((fonts, font_paths)) = loaded.fonts {
+            self.fonts = fonts;
+            self.font_paths = Some(font_paths);
+        }
+
+        self.keyboard = keyboard.enabled.unwrap_or_default();
+
+        self.key_size = keyboard.key_size();
+
+        self.strip = !self.keyboard || keyboard.strip.unwrap_or(true);
+
+        self.show_heatmap = keyboard.heatmap.unwrap_or_default();
+
+        self.report = config.report.clone().unwrap_or_default();
+
+        self.annotations = loaded.annotations;
+
+        self.labels = loaded.labels;
+
+        self.apps.set(loaded.apps);
+
+        let vim = config.vim.clone().unwrap_or_default();
+
+        self.vim = Vim::enabled(&vim);
+
+        self.sequences = loaded.sequences;
+
+        let speed = config.speed.clone().unwrap_or_default();
+
+        let current = self.speed.take();
+
+        self.speed = speed.enabled.unwrap_or_default().then(|| {
+            // keep what was typed so far
+            let mut current = current.unwrap_or_default();
+            current.set_window(speed.window());
+            current
+        });
+
+        self.capture_fallback = config.capture_fallback.unwrap_or_default();
+
+        self.theme = loaded.theme;
+
+        let click_through = config.click_through.unwrap_or_default();
+
+        self.click_through_hotkey = click_through
+            .then(|| {
+                config
+                    .click_through_hotkey
+                    .clone()
+                    .or_else(|| Config::default().click_through_hotkey)
+            })
+            .flatten();
+
+        if click_through != self.click_through {
+            self.set_click_through(click_through);
+        }
+
+        let position = config.position.clone().unwrap_or_default();
+        self.position_changed = position != self.position;
+        self.position = position;
+        self.anchored = self.position.is_anchored(&self.monitors);
+
+        if let Err(e) = self.position.check(&self.monitors) {
+            eprintln!("{e}");
+        }
+
+        self.history
+            .set_erase_on_backspace(config.erase_on_backspace.unwrap_or_default());
+
+        self.profile = config.profile.clone();
+
+        self.profiles = config
+            .profiles
+            .iter()
+            .flatten()
+            .map(|(name, _)| name.clone())
+            .collect();
+
+        self.profile_hotkey = (!self.profiles.is_empty())
+            .then(|| {
+                config
+                    .profile_hotkey
+                    .clone()
+                    .or_else(|| Config::default().profile_hotkey)
+            })
+            .flatten();
+
+        // the page follows the window
+        if let Some(server) = &self.server {
+            server.set_style(self.page_style());
+        }
+    }
+
+    /// How the overlay page looks, like the window
+    fn page_style(&self) -> PageStyle {
+        PageStyle {
+            font_size: self.font_size,
+            width: self.max_width,
+            theme: self.theme,
+            fonts: self.fonts.bytes(),
+        }
+    }
+
+    /// Applies the config file again after it changed,
+    /// the current one stays if it has errors
+    fn reload_config(&mut self) -> Command<Message> {
+        // the window's own write, like a saved position, is in use already
+        let written = self.written_config.as_deref();
+
+        if written.is_some() && std::fs::read_to_string(config_path()).ok().as_deref() == written {
+            return Command::none();
+        }
+
+        match self.read_and_apply_config() {
+            Ok(None) => {}
+            // the profile was removed from the file
+            Ok(Some(warning)) => {
+                eprintln!("{warning}");
+                self.toast(warning);
+            }
+            Err(e) => {
+                eprintln!("{}: {e}", config_path().display());
+                self.toast(format!("config.toml: {e}"));
+                return self.refresh();
+            }
+        }
+
+        self.refresh()
+    }
+
+    /// Reads the config again with the options of the profile
+    fn set_profile(&mut self, name: &str) -> Result<(), String> {
+        let config = read_config(Some(name))?;
+
+        self.try_apply_config(&config)
+    }
+
+    /// Reads the config file with the current profile and applies it,
+    /// returns a warning if the profile is gone
+    fn read_and_apply_config(&mut self) -> Result<Option<String>, String> {
+        let (config, warning) = read_config_or_base(self.profile.as_deref())?;
+
+        self.try_apply_config(&config)?;
+
+        Ok(warning)
+    }
+
+    /// Switches to the next profile and shows its name for a moment
+    fn cycle_profile(&mut self) -> Command<Message> {
+        let Some(name) = profiles::next(&self.profiles, self.profile.as_deref()) else {
+            return Command::none();
+        };
+
+        let name = name.to_string();
+
+        let toast = match self.set_profile(&name) {
+            Ok(()) => format!("Profile: {name}"),
+            Err(e) => {
+                eprintln!("{e}");
+                format!("Profile {name}: {e}")
+            }
+        };
+
+        self.toast(toast);
+
+        self.refresh()
+    }
+
+    /// Shows the text instead of the keys for a moment
+    fn toast(&mut self, text: String) {
+        self.message = Some(text);
+        self.toast_until = Some(Instant::now() + TOAST_DURATION);
+    }
+
+    /// Keys pressed in the window are only shown if the global ones can't be,
+    /// except on platforms where the window doesn't get them globally
+    fn uses_window_keys(&self) -> bool {
+        cfg!(not(target_os = "linux"))
+            || (self.capture_error.is_some() && self.capture_fallback == Fallback::Window)
+    }
+
+    fn set_click_through(&mut self, click_through: bool) {
+        self.click_through = click_through;
+
+        #[cfg(target_os = "linux")]
+        x11::set_click_through(click_through);
+
+        #[cfg(not(target_os = "linux"))]
+        eprintln!("Click-through only works on X11");
+    }
+
+    /// Lets the window be moved while click-through is on
+    fn toggle_click_through(&mut self) -> Command<Message> {
+        self.set_click_through(!self.click_through);
+
+        self.toast(if self.click_through {
+            "click-through on".to_string()
+        } else {
+            "click-through off, right-click to move".to_string()
+        });
+
+        self.refresh()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+
+        #[cfg(target_os = "linux")]
+        if let Some(dbus) = &self.dbus {
+            dbus.paused(paused);
+        }
+    }
+
+    #[cfg(unix)]
+    fn control(&mut self, request: control::Request) -> Command<Message> {
+        use control::Command as Control;
+
+        match &request.command {
+            Control::Pause => self.set_paused(true),
+            Control::Resume => self.set_paused(false),
+            Control::Clear => {
+                self.annotation = None;
+
+                if let Some(vim) = &mut self.vim {
+                    vim.clear();
+                }
+
+                if let Some(sequences) = &mut self.sequences {
+                    sequences.clear();
+                }
+
+                self.board.release_all();
+
+                self.history.clear();
+                self.truncated = false;
+                self.message = None;
+            }
+            Control::ShowText(text) => {
+                self.message = Some(text.clone());
+                self.toast_until = None;
+            }
+            Control::SetMode(mode) => self.mode = *mode,
+            Control::SetProfile(name) => {
+                if let Err(e) = self.set_profile(name) {
+                    request.reply(format!("error: {e}"));
+                    return Command::none();
+                }
+            }
+            Control::ExportHeatmap(dir) => {
+                let dir = dir.clone().unwrap_or_else(data_dir);
+
+                request.reply_written(self.heatmap.export(
+                    &dir,
+                    &self.board,
+                    &self.theme,
+                    &self.fonts,
+                ));
+
+                return Command::none();
+            }
+            Control::Report => {
+                request.reply_written(self.write_report());
+
+                return Command::none();
+            }
+            Control::ReloadConfig => match self.read_and_apply_config() {
+                Ok(None) => {}
+                Ok(Some(warning)) => {
+                    request.reply(format!("ok, {warning}"));
+                    return self.refresh();
+                }
+                Err(e) => {
+                    request.reply(format!("error: {e}"));
+                    return Command::none();
+                }
+            },
+            Control::Status => {
+                request.reply(format!(
+                    "{} mode={} keys={}",
+                    if self.paused { "paused" } else { "running" },
+                    self.mode(),
+                    self.visible_entries().len()
+                ));
+                return Command::none();
+            }
+        }
+
+        request.reply("ok");
+
+        self.refresh()
+    }
+
+    /// Writes the session report to the configured directory
+    fn write_report(&self) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+        let dir = self.report.dir.clone().unwrap_or_else(data_dir);
+
+        self.session.report().write(&dir)
+    }
+
+    fn exit(&mut self) {
+        if self.report.on_exit.unwrap_or_default() {
+            match self.write_report() {
+                Ok(files) => {
+                    for file in files {
+                        println!("Wrote {}", file.display());
+                    }
+                }
+                Err(e) => eprintln!("Could not write the session report: {e}"),
+            }
+        }
+
+        self.exiting = true;
+    }
+
     fn erase_timer(duration: &mut Duration) {
         *duration = Duration::default();
     }
 }
 
 fn main() -> Result<(), iced::Error> {
-    let config_dir = dirs::config_dir().expect("No Config Directory");
+    let cli = Cli::parse();
+
+    let config = match read_config_or_base(cli.profile.as_deref()) {
+        Ok((config, warning)) => {
+            if let Some(warning) = warning {
+                eprintln!("{warning}");
+            }
+            config
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            Config::default()
+        }
+    };
+
+    match &cli.command {
+        #[cfg(unix)]
+        Some(cli::Command::Ctl { command }) => {
+            match control::send(&command.join(" ")) {
+                Ok(response) => println!("{response}"),
+                Err(e) => {
+                    eprintln!("Could not reach zr-alshasha: {e}");
+                    std::process::exit(1);
+                }
+            }
+            return Ok(());
+        }
+        Some(cli::Command::Send { address, secret }) => {
+            let secret = secret
+                .clone()
+                .or_else(|| config.remote.and_then(|remote| remote.secret));
 
-    let config_file = config_dir.join("zr-alshasha/config.toml");
+            let Some(secret) = secret else {
+                eprintln!(
+                    "Sending keys needs a secret, pass --secret or set it in the [remote] section"
+                );
+                std::process::exit(1);
+            };
 
-    let config_str = std::fs::read_to_string(config_file).unwrap_or_default();
+            let hidden = HiddenApps::new(&config.apps.clone().unwrap_or_default());
 
-    let config: Config = from_str(&config_str).unwrap_or_else(|e| {
-        eprintln!("{e}");
-        Config::default()
-    });
+            if let Err(e) = remote::send(address, &secret, hidden) {
+                eprintln!("Could not listen: {e:?}");
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(cli::Command::ImportKeymap {
+            file,
+            format,
+            app,
+            output,
+        }) => {
+            if let Err(e) = import::run(file, *format, app.as_deref(), output.as_deref()) {
+                eprintln!("Could not import {}: {e}", file.display());
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        None => {}
+    }
 
-    let position = config.position.unwrap_or_default();
+    if cli.headless {
+        if let Err(e) = headless::run(&config) {
+            eprintln!("Could not listen: {e:?}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
-    let height = config
-        .font_size
-        .unwrap_or_else(|| Config::default().font_size.unwrap());
+    // the window starts empty, it's sized to the keys once they come
+    let size = (1, 1);
 
-    let width = config
-        .width
-        .unwrap_or_else(|| Config::default().width.unwrap());
+    let (x, y) = config
+        .position
+        .clone()
+        .unwrap_or_default()
+        .resolve(&position::monitors(), size);
 
     let settings = Settings {
         window: iced::window::Settings {
-            size: (width, height + 10),
-            position: Position::Specific(position.x, position.y),
+            size,
+            position: Position::Specific(x, y),
             decorations: false,
             transparent: true,
             always_on_top: true,
-            icon: if let Ok(icon) = image_to_icon() {
-                Some(icon)
-            } else {
-                None
-            },
+            icon: image_to_icon().ok(),
             ..Default::default()
         },
+        flags: config,
+        // so the session report is written first
+        exit_on_close_request: false,
         ..Default::default()
     };
 
@@ -0,0 +1,319 @@
+use std::fmt;
+
+use serde::{Deserialize, Deserializer};
+
+/// Where the window goes, either at `x` and `y` or at an `anchor` of a monitor
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct PositionConfig {
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    /// Takes the place of `x` and `y`, "none" lets a profile use them
+    #[serde(default, deserialize_with = "anchor_or_none")]
+    pub anchor: Option<Anchor>,
+    /// Distance from the anchored edges
+    pub margin_x: Option<i32>,
+    pub margin_y: Option<i32>,
+    pub monitor: Option<MonitorSelector>,
+}
+
+impl Default for PositionConfig {
+    fn default() -> Self {
+        Self {
+            x: Some(1000),
+            y: Some(1000),
+            anchor: Some(Anchor::BottomCenter),
+            margin_x: Some(40),
+            margin_y: Some(40),
+            monitor: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Anchor {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    CenterLeft,
+    Center,
+    CenterRight,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+fn anchor_or_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Anchor>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Setting {
+        Anchor(Anchor),
+        Name(String),
+    }
+
+    match Setting::deserialize(deserializer)? {
+        Setting::Anchor(anchor) => Ok(Some(anchor)),
+        Setting::Name(name) if name == "none" => Ok(None),
+        Setting::Name(name) => Err(serde::de::Error::custom(format!(
+            "unknown anchor \"{name}\""
+        ))),
+    }
+}
+
+/// A monitor by its index, or by its name like "DP-1"
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum MonitorSelector {
+    Index(usize),
+    Name(String),
+}
+
+impl fmt::Display for MonitorSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MonitorSelector::Index(index) => write!(f, "{index}"),
+            MonitorSelector::Name(name) => write!(f, "\"{name}\""),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monitor {
+    pub name: String,
+    pub primary: bool,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl PositionConfig {
+    /// Whether the window follows an anchor, so it's placed again when its size changes
+    pub fn is_anchored(&self, monitors: &[Monitor]) -> bool {
+        self.anchor.is_some() && !monitors.is_empty()
+    }
+
+    /// The top left corner of a window of `size`
+    pub fn resolve(&self, monitors: &[Monitor], size: (u32, u32)) -> (i32, i32) {
+        let default = PositionConfig::default();
+
+        let absolute = (
+            self.x.unwrap_or_else(|| default.x.unwrap()),
+            self.y.unwrap_or_else(|| default.y.unwrap()),
+        );
+
+        let (Some(anchor), Some(monitor)) = (self.anchor, self.monitor(monitors)) else {
+            return absolute;
+        };
+
+        let margin_x = self.margin_x.unwrap_or_else(|| default.margin_x.unwrap());
+        let margin_y = self.margin_y.unwrap_or_else(|| default.margin_y.unwrap());
+
+        let (width, height) = (size.0 as i32, size.1 as i32);
+        let (monitor_width, monitor_height) = (monitor.width as i32, monitor.height as i32);
+
+        let x = match anchor {
+            Anchor::TopLeft | Anchor::CenterLeft | Anchor::BottomLeft => margin_x,
+            Anchor::TopCenter | Anchor::Center | Anchor::BottomCenter => {
+                (monitor_width - width) / 2
+            }
+            Anchor::TopRight | Anchor::CenterRight | Anchor::BottomRight => {
+                monitor_width - width - margin_x
+            }
+        };
+
+        let y = match anchor {
+            Anchor::TopLeft | Anchor::TopCenter | Anchor::TopRight => margin_y,
+            Anchor::CenterLeft | Anchor::Center | Anchor::CenterRight => {
+                (monitor_height - height) / 2
+            }
+            Anchor::BottomLeft | Anchor::BottomCenter | Anchor::BottomRight => {
+                monitor_height - height - margin_y
+            }
+        };
+
+        (monitor.x + x, monitor.y + y)
+    }
+
+    /// Errors if the selected monitor isn't connected
+    pub fn check(&self, monitors: &[Monitor]) -> Result<(), String> {
+        match &self.monitor {
+            Some(selector) if !monitors.is_empty() && self.selected(monitors).is_none() => {
+                let names: Vec<_> = monitors
+                    .iter()
+                    .map(|monitor| monitor.name.as_str())
+                    .collect();
+
+                Err(format!(
+                    "monitor {selector} not found, using the primary one of: {}",
+                    names.join(", ")
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The selected monitor, or the primary one
+    fn monitor<'a>(&self, monitors: &'a [Monitor]) -> Option<&'a Monitor> {
+        self.selected(monitors)
+            .or_else(|| monitors.iter().find(|monitor| monitor.primary))
+            .or_else(|| monitors.first())
+    }
+
+    fn selected<'a>(&self, monitors: &'a [Monitor]) -> Option<&'a Monitor> {
+        match self.monitor.as_ref()? {
+            MonitorSelector::Index(index) => monitors.get(*index),
+            MonitorSelector::Name(name) => monitors.iter().find(|monitor| &monitor.name == name),
+        }
+    }
+}
+
+/// The connected monitors, empty if they can't be queried
+#[cfg(target_os = "linux")]
+pub fn monitors() -> Vec<Monitor> {
+    use x11rb::{
+        connection::Connection, protocol::randr::ConnectionExt as _,
+        protocol::xproto::ConnectionExt as _,
+    };
+
+    let query = || -> Result<Vec<Monitor>, Box<dyn std::error::Error>> {
+        let (connection, screen) = x11rb::connect(None)?;
+        let root = connection.setup().roots[screen].root;
+
+        let reply = connection.randr_get_monitors(root, true)?.reply()?;
+
+        reply
+            .monitors
+            .into_iter()
+            .map(|monitor| {
+                let name = connection.get_atom_name(monitor.name)?.reply()?.name;
+
+                Ok(Monitor {
+                    name: String::from_utf8_lossy(&name).into_owned(),
+                    primary: monitor.primary,
+                    x: monitor.x.into(),
+                    y: monitor.y.into(),
+                    width: monitor.width.into(),
+                    height: monitor.height.into(),
+                })
+            })
+            .collect()
+    };
+
+    query().unwrap_or_else(|e| {
+        eprintln!("Could not get the monitors, using the x and y position: {e}");
+        Vec::new()
+    })
+}
+
+/// The connected monitors, empty if they can't be queried
+#[cfg(not(target_os = "linux"))]
+pub fn monitors() -> Vec<Monitor> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1080p monitor, then a primary 1440p one on its right
+    fn monitors() -> Vec<Monitor> {
+        vec![
+            Monitor {
+                name: "DP-1".to_string(),
+                primary: false,
+                x: 0,
+                y: 0,
+                width: 1920,
+                height: 1080,
+            },
+            Monitor {
+                name: "HDMI-1".to_string(),
+                primary: true,
+                x: 1920,
+                y: 0,
+                width: 2560,
+                height: 1440,
+            },
+        ]
+    }
+
+    fn anchored(anchor: Anchor, monitor: Option<MonitorSelector>) -> PositionConfig {
+        PositionConfig {
+            anchor: Some(anchor),
+            margin_x: Some(40),
+            margin_y: Some(30),
+            monitor,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn anchors_keep_their_margins() {
+        let cases = [
+            (Anchor::TopLeft, (1960, 30)),
+            (Anchor::TopCenter, (3000, 30)),
+            (Anchor::TopRight, (4040, 30)),
+            (Anchor::CenterLeft, (1960, 670)),
+            (Anchor::Center, (3000, 670)),
+            (Anchor::CenterRight, (4040, 670)),
+            (Anchor::BottomLeft, (1960, 1310)),
+            (Anchor::BottomCenter, (3000, 1310)),
+            (Anchor::BottomRight, (4040, 1310)),
+        ];
+
+        for (anchor, position) in cases {
+            assert_eq!(
+                anchored(anchor, None).resolve(&monitors(), (400, 100)),
+                position,
+                "{anchor:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn monitors_are_picked_by_index_or_name() {
+        for selector in [
+            MonitorSelector::Index(0),
+            MonitorSelector::Name("DP-1".to_string()),
+        ] {
+            let position = anchored(Anchor::BottomRight, Some(selector));
+
+            assert_eq!(position.resolve(&monitors(), (400, 100)), (1480, 950));
+            assert!(position.check(&monitors()).is_ok());
+        }
+    }
+
+    #[test]
+    fn unknown_monitors_fall_back_to_the_primary_one() {
+        for selector in [
+            MonitorSelector::Index(5),
+            MonitorSelector::Name("VGA-1".to_string()),
+        ] {
+            let position = anchored(Anchor::TopLeft, Some(selector));
+
+            assert_eq!(position.resolve(&monitors(), (400, 100)), (1960, 30));
+            assert!(position.check(&monitors()).is_err());
+        }
+    }
+
+    #[test]
+    fn without_an_anchor_x_and_y_are_used() {
+        let position: PositionConfig =
+            toml::from_str("x = 15\ny = -20\nanchor = \"none\"\nmonitor = 1").unwrap();
+
+        assert_eq!(position.anchor, None);
+        assert!(!position.is_anchored(&monitors()));
+        assert_eq!(position.resolve(&monitors(), (400, 100)), (15, -20));
+
+        // an anchor needs a monitor to be placed on
+        let position = PositionConfig {
+            x: Some(15),
+            y: Some(-20),
+            ..anchored(Anchor::Center, None)
+        };
+        assert_eq!(position.resolve(&[], (400, 100)), (15, -20));
+
+        assert!(toml::from_str::<PositionConfig>("anchor = \"middle\"").is_err());
+    }
+}
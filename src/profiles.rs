@@ -0,0 +1,130 @@
+use toml::{value::Table, Value};
+
+/// Puts the options of `[profiles.<name>]` over the top-level ones,
+/// the profile is the `profile` option unless `name` is given
+pub fn apply(config: &mut Table, name: Option<&str>) -> Result<(), String> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => match config.get("profile") {
+            Some(Value::String(name)) => name.clone(),
+            Some(_) => return Err("profile is not a string".to_string()),
+            None => return Ok(()),
+        },
+    };
+
+    let profile = config
+        .get("profiles")
+        .and_then(|profiles| profiles.get(&name))
+        .ok_or_else(|| format!("unknown profile \"{name}\""))?
+        .as_table()
+        .ok_or_else(|| format!("[profiles.{name}] is not a table"))?
+        .clone();
+
+    merge(config, profile);
+
+    // the profile in use, for cycling through them
+    config.insert("profile".to_string(), Value::String(name));
+
+    Ok(())
+}
+
+/// Like `apply`, but a profile that can't be used leaves the top-level options,
+/// returns the warning for it
+pub fn apply_or_base(config: &mut Table, name: Option<&str>) -> Option<String> {
+    // nothing is merged before an error
+    let e = apply(config, name).err()?;
+
+    config.remove("profile");
+
+    Some(format!("{e}, using the options outside the profiles"))
+}
+
+/// Tables are merged key by key, other values are replaced
+fn merge(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(value)) => merge(base, value),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// The profile after `current` in name order, the first one after the last
+pub fn next<'a>(names: &'a [String], current: Option<&str>) -> Option<&'a str> {
+    let index = current
+        .and_then(|current| names.iter().position(|name| name == current))
+        .map_or(0, |index| (index + 1) % names.len().max(1));
+
+    names.get(index).map(String::as_str)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        width = 700
+        profile = "talk"
+
+        [position]
+        anchor = "bottom-center"
+        margin_y = 40
+
+        [profiles.talk]
+        width = 900
+
+        [profiles.talk.position]
+        margin_y = 80
+
+        [profiles.code]
+        mode = "shortcuts"
+    "#;
+
+    fn config() -> Table {
+        toml::from_str(CONFIG).unwrap()
+    }
+
+    #[test]
+    fn profiles_are_merged_over_the_top_level() {
+        let mut config = config();
+        apply(&mut config, None).unwrap();
+
+        assert_eq!(config["width"].as_integer(), Some(900));
+        assert_eq!(config["position"]["margin_y"].as_integer(), Some(80));
+        assert_eq!(config["position"]["anchor"].as_str(), Some("bottom-center"));
+
+        let mut config = self::config();
+        apply(&mut config, Some("code")).unwrap();
+
+        assert_eq!(config["width"].as_integer(), Some(700));
+        assert_eq!(config["profile"].as_str(), Some("code"));
+    }
+
+    #[test]
+    fn unknown_profiles_leave_the_top_level() {
+        let mut config = config();
+        assert!(apply(&mut config, Some("missing")).is_err());
+
+        let mut config = self::config();
+        let warning = apply_or_base(&mut config, Some("missing"));
+
+        assert!(warning.is_some_and(|warning| warning.contains("missing")));
+        assert_eq!(config["width"].as_integer(), Some(700));
+        assert!(!config.contains_key("profile"));
+
+        let mut config = self::config();
+        assert_eq!(apply_or_base(&mut config, Some("talk")), None);
+    }
+
+    #[test]
+    fn profiles_are_cycled_in_order() {
+        let names = ["code", "talk"].map(String::from);
+
+        assert_eq!(next(&names, None), Some("code"));
+        assert_eq!(next(&names, Some("code")), Some("talk"));
+        assert_eq!(next(&names, Some("talk")), Some("code"));
+        assert_eq!(next(&[], None), None);
+    }
+}
@@ -0,0 +1,369 @@
+use std::{
+    io::{self, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::mpsc as std_mpsc,
+    time::{Duration, SystemTime},
+};
+
+use iced::{
+    futures::{channel::mpsc, StreamExt},
+    Subscription,
+};
+use iced_native::subscription;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use snow::{HandshakeState, TransportState};
+
+use crate::{apps::HiddenApps, keys};
+
+/// How long the other side has to finish the handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// How long the sender waits before connecting again
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+/// Both sides prove they know the secret, then every event is encrypted,
+/// the ephemeral keys keep the secret from being guessed from a recording
+const NOISE_PARAMS: &str = "Noise_NNpsk0_25519_ChaChaPoly_SHA256";
+/// The longest Noise message
+const MAX_MESSAGE_LEN: usize = 65535;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RemoteConfig {
+    /// Where to accept key events from `zr-alshasha send`
+    pub listen: Option<String>,
+    pub secret: Option<String>,
+    /// Keep showing this machine's keys too
+    pub local_keys: Option<bool>,
+}
+
+pub enum State {
+    Starting { address: String, secret: String },
+    Ready(mpsc::UnboundedReceiver<rdev::Event>),
+    Finished,
+}
+
+/// Accepts key events forwarded by `zr-alshasha send` on another machine
+pub fn receive(address: String, secret: String) -> Subscription<keys::Event> {
+    struct Remote;
+
+    subscription::unfold(
+        (std::any::TypeId::of::<Remote>(), address.clone()),
+        State::Starting { address, secret },
+        |state| async move {
+            match state {
+                State::Starting { address, secret } => {
+                    let listener = match TcpListener::bind(&address) {
+                        Ok(listener) => listener,
+                        Err(e) => {
+                            eprintln!("Could not listen for remote keys on {address}: {e}");
+                            return (None, State::Finished);
+                        }
+                    };
+
+                    let (sender, receiver) = mpsc::unbounded();
+
+                    std::thread::spawn(move || {
+                        for stream in listener.incoming().flatten() {
+                            let sender = sender.clone();
+                            let secret = secret.clone();
+
+                            std::thread::spawn(move || {
+                                let peer = stream.peer_addr();
+                                if let Err(e) = handle(stream, &secret, sender) {
+                                    eprintln!("Remote keys from {peer:?} stopped: {e}");
+                                }
+                            });
+                        }
+                    });
+
+                    (Some(keys::Event::Ready), State::Ready(receiver))
+                }
+                State::Ready(mut input) => match input.next().await {
+                    Some(event) => (Some(keys::Event::EventRecieved(event)), State::Ready(input)),
+                    None => (None, State::Finished),
+                },
+                State::Finished => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+fn handle(
+    stream: TcpStream,
+    secret: &str,
+    sender: mpsc::UnboundedSender<rdev::Event>,
+) -> io::Result<()> {
+    let mut channel = Channel::accept(stream, secret)?;
+
+    while let Some(message) = channel.receive()? {
+        let mut event: rdev::Event = serde_json::from_slice(&message)?;
+
+        // the other machine's clock may not match, and typing speed is measured on this one
+        event.time = SystemTime::now();
+
+        sender
+            .unbounded_send(event)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "app is closing"))?;
+    }
+
+    Ok(())
+}
+
+/// Captures keys on this machine and forwards them to the overlay at `address`,
+/// connecting again whenever the connection drops, keys typed in `hidden` apps stay here
+pub fn send(address: &str, secret: &str, hidden: HiddenApps) -> Result<(), rdev::ListenError> {
+    let (sender, receiver) = std_mpsc::channel();
+
+    let address = address.to_string();
+    let secret = secret.to_string();
+
+    std::thread::spawn(move || loop {
+        match Channel::connect(&address, &secret) {
+            Ok(mut channel) => {
+                println!("Sending keys to {address}");
+
+                // don't replay what was typed while disconnected
+                receiver.try_iter().for_each(drop);
+
+                if let Err(e) = forward(&mut channel, &receiver) {
+                    eprintln!("Lost connection to {address}: {e}");
+                }
+            }
+            Err(e) => eprintln!("Could not connect to {address}: {e}"),
+        }
+
+        std::thread::sleep(RECONNECT_DELAY);
+    });
+
+    rdev::listen(move |event| {
+        let send = match event.event_type {
+            rdev::EventType::KeyPress(_) => !hidden.are_focused(),
+            // releases still go out, so no key is left held on the other side
+            rdev::EventType::KeyRelease(_) => true,
+            _ => false,
+        };
+
+        if send {
+            let _ = sender.send(event);
+        }
+    })
+}
+
+fn forward(channel: &mut Channel, receiver: &std_mpsc::Receiver<rdev::Event>) -> io::Result<()> {
+    for event in receiver {
+        channel.send(&serde_json::to_vec(&event)?)?;
+    }
+
+    Ok(())
+}
+
+/// An encrypted connection between a sender and the overlay,
+/// made once both proved they know the secret
+struct Channel {
+    stream: TcpStream,
+    noise: TransportState,
+    buffer: Vec<u8>,
+}
+
+impl Channel {
+    /// Connects to the overlay, failing unless it knows the secret too
+    fn connect(address: &str, secret: &str) -> io::Result<Self> {
+        let mut stream = TcpStream::connect(address)?;
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+        let mut noise = handshake(secret, true)?;
+        let mut buffer = vec![0; MAX_MESSAGE_LEN];
+
+        let len = noise.write_message(&[], &mut buffer).map_err(noise_error)?;
+        write_frame(&mut stream, &buffer[..len])?;
+
+        let message = read_frame(&mut stream)?;
+        noise.read_message(&message, &mut buffer).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "the overlay doesn't know the secret",
+            )
+        })?;
+
+        Self::start(stream, noise, buffer)
+    }
+
+    /// Accepts a sender, failing unless it knows the secret
+    fn accept(mut stream: TcpStream, secret: &str) -> io::Result<Self> {
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+
+        let mut noise = handshake(secret, false)?;
+        let mut buffer = vec![0; MAX_MESSAGE_LEN];
+
+        let message = read_frame(&mut stream)?;
+        noise
+            .read_message(&message, &mut buffer)
+            .map_err(|_| io::Error::new(io::ErrorKind::PermissionDenied, "wrong secret"))?;
+
+        let len = noise.write_message(&[], &mut buffer).map_err(noise_error)?;
+        write_frame(&mut stream, &buffer[..len])?;
+
+        Self::start(stream, noise, buffer)
+    }
+
+    fn start(stream: TcpStream, noise: HandshakeState, buffer: Vec<u8>) -> io::Result<Self> {
+        stream.set_read_timeout(None)?;
+
+        Ok(Self {
+            stream,
+            noise: noise.into_transport_mode().map_err(noise_error)?,
+            buffer,
+        })
+    }
+
+    fn send(&mut self, message: &[u8]) -> io::Result<()> {
+        let len = self
+            .noise
+            .write_message(message, &mut self.buffer)
+            .map_err(noise_error)?;
+
+        write_frame(&mut self.stream, &self.buffer[..len])
+    }
+
+    /// The next message, or none once the other side closed the connection
+    fn receive(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let message = match read_frame(&mut self.stream) {
+            Ok(message) => message,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        // fails for anything not sent by the other side, in order
+        let len = self
+            .noise
+            .read_message(&message, &mut self.buffer)
+            .map_err(noise_error)?;
+
+        Ok(Some(self.buffer[..len].to_vec()))
+    }
+}
+
+/// The handshake of the sender, or of the overlay, keyed by the secret
+fn handshake(secret: &str, sender: bool) -> io::Result<HandshakeState> {
+    let psk = Sha256::digest(secret.as_bytes());
+
+    let builder = snow::Builder::new(NOISE_PARAMS.parse().map_err(noise_error)?).psk(0, &psk);
+
+    if sender {
+        builder.build_initiator()
+    } else {
+        builder.build_responder()
+    }
+    .map_err(noise_error)
+}
+
+/// A message, after its length
+fn write_frame(stream: &mut TcpStream, message: &[u8]) -> io::Result<()> {
+    let len = u16::try_from(message.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too long"))?;
+
+    stream.write_all(&len.to_be_bytes())?;
+    stream.write_all(message)
+}
+
+fn read_frame(stream: &mut TcpStream) -> io::Result<Vec<u8>> {
+    let mut len = [0; 2];
+    stream.read_exact(&mut len)?;
+
+    let mut message = vec![0; usize::from(u16::from_be_bytes(len))];
+    stream.read_exact(&mut message)?;
+
+    Ok(message)
+}
+
+fn noise_error(e: snow::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::JoinHandle;
+
+    use super::*;
+
+    /// An overlay on a free port accepting one sender, and its address
+    fn overlay(secret: &'static str) -> (String, JoinHandle<io::Result<Channel>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let accepted = std::thread::spawn(move || {
+            let (stream, _) = listener.accept()?;
+            Channel::accept(stream, secret)
+        });
+
+        (address, accepted)
+    }
+
+    #[test]
+    fn events_arrive_with_the_same_secret() {
+        let (address, accepted) = overlay("change me");
+
+        let mut sender = Channel::connect(&address, "change me").unwrap();
+        let mut overlay = accepted.join().unwrap().unwrap();
+
+        sender.send(b"first").unwrap();
+        sender.send(b"second").unwrap();
+        drop(sender);
+
+        assert_eq!(overlay.receive().unwrap().as_deref(), Some(&b"first"[..]));
+        assert_eq!(overlay.receive().unwrap().as_deref(), Some(&b"second"[..]));
+        assert_eq!(overlay.receive().unwrap(), None);
+    }
+
+    #[test]
+    fn both_sides_refuse_a_wrong_secret() {
+        let (address, accepted) = overlay("change me");
+
+        let sent = Channel::connect(&address, "guess");
+        let received = accepted.join().unwrap();
+
+        assert_eq!(
+            received.err().map(|e| e.kind()),
+            Some(io::ErrorKind::PermissionDenied)
+        );
+        // the overlay hung up instead of answering
+        assert!(sent.is_err());
+    }
+
+    #[test]
+    fn the_sender_refuses_an_overlay_without_the_secret() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // an overlay that answers without the secret, with a key and a made up tag
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let message = read_frame(&mut stream).unwrap();
+            let _ = write_frame(&mut stream, &message);
+        });
+
+        let sent = Channel::connect(&address, "change me");
+
+        assert_eq!(
+            sent.err().map(|e| e.kind()),
+            Some(io::ErrorKind::PermissionDenied)
+        );
+    }
+
+    #[test]
+    fn changed_messages_are_refused() {
+        let (address, accepted) = overlay("change me");
+
+        let mut sender = Channel::connect(&address, "change me").unwrap();
+        let mut overlay = accepted.join().unwrap().unwrap();
+
+        let len = sender
+            .noise
+            .write_message(b"key", &mut sender.buffer)
+            .unwrap();
+        let mut message = sender.buffer[..len].to_vec();
+        message[0] ^= 1;
+        write_frame(&mut sender.stream, &message).unwrap();
+
+        assert!(overlay.receive().is_err());
+    }
+}
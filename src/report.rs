@@ -0,0 +1,324 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    error::Error,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    history,
+    hotkey::Hotkey,
+    keys::{Keys, Modifier},
+};
+
+/// Presses further apart than this are a break, not typing
+const IDLE_GAP: Duration = Duration::from_secs(5);
+
+const TOP_CHORDS: usize = 20;
+const TOP_KEYS: usize = 20;
+const TOP_RUNS: usize = 10;
+
+/// The `[report]` section
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ReportConfig {
+    /// Write the report when the app exits
+    pub on_exit: Option<bool>,
+    /// Where reports are written, the data directory by default
+    pub dir: Option<PathBuf>,
+}
+
+/// Everything pressed since the app started, summed up in a [`Report`]
+#[derive(Debug, Clone)]
+pub struct Session {
+    started: SystemTime,
+    last_press: Option<SystemTime>,
+    active: Duration,
+    total_keys: u64,
+    backspaces: u64,
+    /// By their name, like "Ctrl+Shift+P"
+    chords: HashMap<String, u64>,
+    keys: HashMap<String, u64>,
+    /// The key pressed last and how many times in a row
+    run: Option<(String, u64)>,
+    /// The longest run of every key
+    runs: HashMap<String, u64>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            started: SystemTime::now(),
+            last_press: None,
+            active: Duration::default(),
+            total_keys: 0,
+            backspaces: 0,
+            chords: HashMap::new(),
+            keys: HashMap::new(),
+            run: None,
+            runs: HashMap::new(),
+        }
+    }
+}
+
+impl Session {
+    /// A key pressed at `time` while holding `held`
+    pub fn press(&mut self, key: rdev::Key, held: &BTreeSet<Modifier>, time: SystemTime) {
+        if let Some(gap) = self
+            .last_press
+            .and_then(|last_press| time.duration_since(last_press).ok())
+        {
+            if gap < IDLE_GAP {
+                self.active += gap;
+            }
+        }
+
+        self.last_press = Some(time);
+        self.total_keys += 1;
+
+        if key == rdev::Key::Backspace {
+            self.backspaces += 1;
+        }
+
+        // modifiers are counted with the key they're held for
+        if key.modifier().is_some() {
+            return;
+        }
+
+        let name = Hotkey {
+            modifiers: held.clone(),
+            key,
+        }
+        .to_string();
+
+        // shift only changes the typed letter
+        let counts = if !history::is_shortcut(held) {
+            &mut self.keys
+        } else {
+            &mut self.chords
+        };
+
+        *counts.entry(name.clone()).or_default() += 1;
+
+        let run = match self.run.take() {
+            Some((last, length)) if last == name => (name, length + 1),
+            _ => (name, 1),
+        };
+
+        let longest = self.runs.entry(run.0.clone()).or_default();
+        *longest = (*longest).max(run.1);
+
+        self.run = Some(run);
+    }
+
+    pub fn report(&self) -> Report {
+        let now = SystemTime::now();
+        let seconds = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        };
+
+        Report {
+            started: seconds(self.started),
+            ended: seconds(now),
+            total_keys: self.total_keys,
+            active_seconds: self.active.as_secs(),
+            backspace_ratio: if self.total_keys == 0 {
+                0.0
+            } else {
+                self.backspaces as f64 / self.total_keys as f64
+            },
+            top_chords: top(&self.chords, TOP_CHORDS),
+            top_keys: top(&self.keys, TOP_KEYS),
+            // a run of one isn't a run
+            longest_runs: top(&self.runs, TOP_RUNS)
+                .into_iter()
+                .filter(|run| run.count > 1)
+                .collect(),
+        }
+    }
+}
+
+/// The most counted names first
+fn top(counts: &HashMap<String, u64>, limit: usize) -> Vec<Count> {
+    let mut top: Vec<_> = counts
+        .iter()
+        .map(|(keys, count)| Count {
+            keys: keys.clone(),
+            count: *count,
+        })
+        .collect();
+
+    top.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.keys.cmp(&b.keys)));
+    top.truncate(limit);
+
+    top
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Count {
+    pub keys: String,
+    pub count: u64,
+}
+
+/// What was pressed in a session, times are unix seconds
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub started: u64,
+    pub ended: u64,
+    pub total_keys: u64,
+    /// Time spent pressing keys, without the breaks
+    pub active_seconds: u64,
+    /// Backspaces out of all key presses
+    pub backspace_ratio: f64,
+    pub top_chords: Vec<Count>,
+    pub top_keys: Vec<Count>,
+    /// Keys pressed the most times in a row
+    pub longest_runs: Vec<Count>,
+}
+
+impl Report {
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = String::from("# Session report\n\n");
+
+        let active = Duration::from_secs(self.active_seconds);
+
+        // writing to a string can't fail
+        let _ = writeln!(markdown, "- Total keys: {}", self.total_keys);
+        let _ = writeln!(
+            markdown,
+            "- Active time: {}h {}m {}s",
+            active.as_secs() / 3600,
+            active.as_secs() / 60 % 60,
+            active.as_secs() % 60
+        );
+        let _ = writeln!(
+            markdown,
+            "- Backspace ratio: {:.1}%",
+            self.backspace_ratio * 100.0
+        );
+
+        for (title, counts, unit) in [
+            ("Top chords", &self.top_chords, ""),
+            ("Top keys", &self.top_keys, ""),
+            ("Longest repeat runs", &self.longest_runs, " in a row"),
+        ] {
+            if counts.is_empty() {
+                continue;
+            }
+
+            let _ = write!(markdown, "\n## {title}\n\n");
+
+            for count in counts {
+                let _ = writeln!(markdown, "- `{}`: {}{unit}", count.keys, count.count);
+            }
+        }
+
+        markdown
+    }
+
+    /// Writes the report as Markdown and JSON to `dir`, named after when the session started,
+    /// returns the written files
+    pub fn write(&self, dir: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+        std::fs::create_dir_all(dir)?;
+
+        let path = |extension: &str| dir.join(format!("session-{}.{extension}", self.started));
+
+        let markdown = path("md");
+        std::fs::write(&markdown, self.to_markdown())?;
+
+        let json = path("json");
+        std::fs::write(&json, serde_json::to_string_pretty(self)?)?;
+
+        Ok(vec![markdown, json])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(counts: &[Count]) -> Vec<(&str, u64)> {
+        counts
+            .iter()
+            .map(|count| (count.keys.as_str(), count.count))
+            .collect()
+    }
+
+    #[test]
+    fn the_report_sums_up_the_presses() {
+        let start = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let at = |millis: u64| start + Duration::from_millis(millis);
+
+        let mut session = Session {
+            started: start,
+            ..Default::default()
+        };
+
+        let none = BTreeSet::new();
+        let control = BTreeSet::from([Modifier::Control]);
+        let shift = BTreeSet::from([Modifier::Shift]);
+
+        session.press(rdev::Key::KeyA, &none, at(0));
+        session.press(rdev::Key::KeyA, &none, at(1000));
+        session.press(rdev::Key::KeyA, &none, at(2000));
+        session.press(rdev::Key::Backspace, &none, at(3000));
+        // a break
+        session.press(rdev::Key::ControlLeft, &control, at(20_000));
+        session.press(rdev::Key::KeyC, &control, at(20_500));
+        session.press(rdev::Key::ShiftLeft, &shift, at(21_000));
+        session.press(rdev::Key::KeyB, &shift, at(21_200));
+
+        let report = session.report();
+
+        assert_eq!(report.started, 1_700_000_000);
+        assert_eq!(report.total_keys, 8);
+        assert_eq!(report.active_seconds, 4);
+        assert_eq!(report.backspace_ratio, 0.125);
+        assert_eq!(names(&report.top_chords), [("Ctrl+C", 1)]);
+        assert_eq!(
+            names(&report.top_keys),
+            [("A", 3), ("Backspace", 1), ("Shift+B", 1)]
+        );
+        assert_eq!(names(&report.longest_runs), [("A", 3)]);
+    }
+
+    #[test]
+    fn runs_are_broken_by_other_keys() {
+        let start = UNIX_EPOCH;
+        let mut session = Session::default();
+        let none = BTreeSet::new();
+
+        for (i, key) in [
+            rdev::Key::KeyX,
+            rdev::Key::KeyX,
+            rdev::Key::KeyY,
+            rdev::Key::KeyX,
+            rdev::Key::KeyY,
+            rdev::Key::KeyY,
+            rdev::Key::KeyY,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            session.press(key, &none, start + Duration::from_secs(i as u64));
+        }
+
+        let report = session.report();
+
+        assert_eq!(names(&report.longest_runs), [("Y", 3), ("X", 2)]);
+        assert_eq!(names(&report.top_keys), [("Y", 4), ("X", 3)]);
+    }
+
+    #[test]
+    fn an_empty_session_has_no_ratio() {
+        let report = Session::default().report();
+
+        assert_eq!(report.total_keys, 0);
+        assert_eq!(report.backspace_ratio, 0.0);
+        assert!(report.top_keys.is_empty() && report.longest_runs.is_empty());
+    }
+}
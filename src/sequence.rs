@@ -0,0 +1,387 @@
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::{
+    history,
+    hotkey::{parse_key, Hotkey},
+    keys::Modifier,
+};
+
+/// The `[sequences]` section
+#[derive(Debug, Clone, Deserialize)]
+pub struct SequenceConfig {
+    pub enabled: Option<bool>,
+    /// Milliseconds a sequence waits for its next chord
+    pub timeout: Option<u64>,
+    /// Sequences that keys without modifiers continue, like "C-x" in "C-x b"
+    pub prefixes: Option<Vec<String>>,
+}
+
+impl Default for SequenceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Some(false),
+            timeout: Some(1000),
+            prefixes: Some(
+                [
+                    "C-x", "C-c", "C-h", "M-g", "C-x 4", "C-x 5", "C-x 8", "C-x n", "C-x r",
+                    "C-x t",
+                ]
+                .map(String::from)
+                .to_vec(),
+            ),
+        }
+    }
+}
+
+impl SequenceConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_millis(
+            self.timeout
+                .unwrap_or_else(|| SequenceConfig::default().timeout.unwrap()),
+        )
+    }
+}
+
+/// Chords pressed one after another, shown as one entry like "C-x C-s"
+#[derive(Debug, Clone)]
+pub struct Sequence {
+    pub keys: Vec<Hotkey>,
+    /// The sequence replaces the entry of its shorter self
+    pub extended: bool,
+}
+
+impl Sequence {
+    /// In Emacs notation, like "C-x C-s"
+    pub fn name(&self) -> String {
+        self.keys.iter().map(to_emacs).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Groups chords pressed within the timeout of each other into sequences
+#[derive(Debug, Clone, Default)]
+pub struct Sequences {
+    timeout: Duration,
+    prefixes: Vec<Vec<Hotkey>>,
+    current: Vec<Hotkey>,
+    last_press: Option<Instant>,
+}
+
+impl Sequences {
+    /// `None` unless it's turned on in the config
+    pub fn enabled(config: &SequenceConfig) -> Result<Option<Self>, String> {
+        config
+            .enabled
+            .unwrap_or_default()
+            .then(|| Self::new(config))
+            .transpose()
+    }
+
+    pub fn new(config: &SequenceConfig) -> Result<Self, String> {
+        let prefixes = config
+            .prefixes
+            .clone()
+            .unwrap_or_else(|| SequenceConfig::default().prefixes.unwrap())
+            .iter()
+            .map(|prefix| parse_sequence(prefix).map_err(|e| format!("Sequence prefix {e}")))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            timeout: config.timeout(),
+            prefixes,
+            ..Default::default()
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.current.clear();
+    }
+
+    /// The sequence `hotkey` starts or continues, if it's a chord or follows a prefix,
+    /// `continues` tells if other sequences start with the keys
+    pub fn press(
+        &mut self,
+        hotkey: Hotkey,
+        now: Instant,
+        continues: impl Fn(&[Hotkey]) -> bool,
+    ) -> Option<Sequence> {
+        let chord = history::is_shortcut(&hotkey.modifiers);
+
+        let in_time = self
+            .last_press
+            .is_some_and(|last_press| now.duration_since(last_press) <= self.timeout);
+
+        let is_prefix = self.prefixes.contains(&self.current) || continues(&self.current);
+
+        self.last_press = Some(now);
+
+        if !self.current.is_empty() && in_time && (chord || is_prefix) {
+            self.current.push(hotkey);
+
+            let sequence = Sequence {
+                keys: self.current.clone(),
+                extended: true,
+            };
+
+            // "C-x b" is done, unless it's a prefix too
+            if !chord && !self.prefixes.contains(&self.current) && !continues(&self.current) {
+                self.current.clear();
+            }
+
+            return Some(sequence);
+        }
+
+        if !chord {
+            self.current.clear();
+            return None;
+        }
+
+        self.current = vec![hotkey];
+
+        Some(Sequence {
+            keys: self.current.clone(),
+            extended: false,
+        })
+    }
+}
+
+/// Chords like "Ctrl+X Ctrl+S" or "C-x C-s", one chord is a sequence too
+pub fn parse_sequence(sequence: &str) -> Result<Vec<Hotkey>, String> {
+    // "Ctrl + P" is one chord
+    if let Ok(hotkey) = sequence.parse() {
+        return Ok(vec![hotkey]);
+    }
+
+    sequence
+        .split_whitespace()
+        .map(|chord| chord.parse().or_else(|e| parse_emacs(chord).ok_or(e)))
+        .collect()
+}
+
+/// A chord in Emacs notation, like "C-x", "M-<left>" or "RET"
+fn parse_emacs(chord: &str) -> Option<Hotkey> {
+    let mut hotkey = Hotkey {
+        modifiers: Default::default(),
+        key: rdev::Key::Unknown(0),
+    };
+
+    let mut rest = chord;
+
+    // "C--" is control and minus
+    while rest.len() > 2 && rest.as_bytes()[1] == b'-' {
+        let modifier = match rest.as_bytes()[0] {
+            b'C' => Modifier::Control,
+            b'M' => Modifier::Alt,
+            b'S' => Modifier::Shift,
+            b's' => Modifier::Meta,
+            _ => return None,
+        };
+
+        hotkey.modifiers.insert(modifier);
+        rest = &rest[2..];
+    }
+
+    let name = rest
+        .strip_prefix('<')
+        .and_then(|name| name.strip_suffix('>'))
+        .unwrap_or(rest);
+
+    hotkey.key = match name {
+        "RET" | "return" => rdev::Key::Return,
+        "TAB" | "tab" => rdev::Key::Tab,
+        "SPC" => rdev::Key::Space,
+        "ESC" | "escape" => rdev::Key::Escape,
+        "DEL" | "backspace" => rdev::Key::Backspace,
+        "prior" => rdev::Key::PageUp,
+        "next" => rdev::Key::PageDown,
+        name => {
+            // "X" is shift and x
+            if name.len() == 1 && name.chars().all(|char| char.is_ascii_uppercase()) {
+                hotkey.modifiers.insert(Modifier::Shift);
+            }
+
+            parse_key(name)?
+        }
+    };
+
+    Some(hotkey)
+}
+
+/// A chord in Emacs notation, like "C-x" or "M-<left>"
+pub fn to_emacs(hotkey: &Hotkey) -> String {
+    let letter = format!("{:?}", hotkey.key)
+        .strip_prefix("Key")
+        .filter(|letter| letter.len() == 1)
+        .map(String::from);
+
+    let shift = hotkey.modifiers.contains(&Modifier::Shift);
+    let others = hotkey
+        .modifiers
+        .iter()
+        .any(|modifier| *modifier != Modifier::Shift);
+
+    // Emacs's own order
+    let mut name: String = [
+        (Modifier::Control, "C-"),
+        (Modifier::Alt, "M-"),
+        (Modifier::Shift, "S-"),
+        (Modifier::Meta, "s-"),
+    ]
+    .into_iter()
+    .filter(|(modifier, _)| hotkey.modifiers.contains(modifier))
+    // a shifted letter alone is just the capital letter
+    .filter(|(modifier, _)| *modifier != Modifier::Shift || letter.is_none() || others)
+    .map(|(_, prefix)| prefix)
+    .collect();
+
+    let key = match (&letter, hotkey.key) {
+        (Some(letter), _) if shift && !others => letter.to_uppercase(),
+        (Some(letter), _) => letter.to_lowercase(),
+        (None, rdev::Key::Return | rdev::Key::KpReturn) => "RET".to_string(),
+        (None, rdev::Key::Tab) => "TAB".to_string(),
+        (None, rdev::Key::Space) => "SPC".to_string(),
+        (None, rdev::Key::Escape) => "ESC".to_string(),
+        (None, rdev::Key::Backspace) => "DEL".to_string(),
+        (None, rdev::Key::PageUp) => "<prior>".to_string(),
+        (None, rdev::Key::PageDown) => "<next>".to_string(),
+        (None, rdev::Key::Minus) => "-".to_string(),
+        (None, rdev::Key::Equal) => "=".to_string(),
+        (None, rdev::Key::LeftBracket) => "[".to_string(),
+        (None, rdev::Key::RightBracket) => "]".to_string(),
+        (None, rdev::Key::SemiColon) => ";".to_string(),
+        (None, rdev::Key::Quote) => "'".to_string(),
+        (None, rdev::Key::Comma) => ",".to_string(),
+        (None, rdev::Key::Dot) => ".".to_string(),
+        (None, rdev::Key::Slash) => "/".to_string(),
+        (None, rdev::Key::BackSlash) => "\\".to_string(),
+        (None, rdev::Key::BackQuote) => "`".to_string(),
+        (None, key) => {
+            let name = Hotkey {
+                modifiers: Default::default(),
+                key,
+            }
+            .to_string();
+
+            match name.len() {
+                // digits
+                1 => name,
+                _ => format!("<{}>", name.trim_end_matches("Arrow").to_lowercase()),
+            }
+        }
+    };
+
+    name.push_str(&key);
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chord(modifiers: &[Modifier], key: rdev::Key) -> Hotkey {
+        Hotkey {
+            modifiers: modifiers.iter().copied().collect(),
+            key,
+        }
+    }
+
+    #[test]
+    fn emacs_chords_are_parsed() {
+        use Modifier::*;
+
+        assert_eq!(parse_emacs("C-x"), Some(chord(&[Control], rdev::Key::KeyX)));
+        assert_eq!(
+            parse_emacs("C-M-<left>"),
+            Some(chord(&[Control, Alt], rdev::Key::LeftArrow))
+        );
+        assert_eq!(
+            parse_emacs("C--"),
+            Some(chord(&[Control], rdev::Key::Minus))
+        );
+        assert_eq!(parse_emacs("RET"), Some(chord(&[], rdev::Key::Return)));
+        assert_eq!(parse_emacs("s-SPC"), Some(chord(&[Meta], rdev::Key::Space)));
+        assert_eq!(parse_emacs("X"), Some(chord(&[Shift], rdev::Key::KeyX)));
+
+        assert_eq!(parse_emacs("Q-x"), None);
+        assert_eq!(parse_emacs("C-<nope>"), None);
+    }
+
+    #[test]
+    fn emacs_names_read_back() {
+        for name in ["C-x", "C-M-<left>", "C--", "RET", "X", "M-S-x", "C-<f5>"] {
+            let hotkey = parse_emacs(name).unwrap();
+            assert_eq!(to_emacs(&hotkey), name);
+        }
+    }
+
+    #[test]
+    fn sequences_mix_both_notations() {
+        assert_eq!(
+            parse_sequence("Ctrl+X C-s").unwrap(),
+            [
+                chord(&[Modifier::Control], rdev::Key::KeyX),
+                chord(&[Modifier::Control], rdev::Key::KeyS)
+            ]
+        );
+        assert!(parse_sequence("C-x nope").is_err());
+    }
+
+    #[test]
+    fn chords_within_the_timeout_are_grouped() {
+        let mut sequences = Sequences::new(&SequenceConfig::default()).unwrap();
+        let start = Instant::now();
+        let never = |_: &[Hotkey]| false;
+
+        let control = |key| chord(&[Modifier::Control], key);
+
+        let first = sequences
+            .press(control(rdev::Key::KeyX), start, never)
+            .unwrap();
+        assert!(!first.extended);
+
+        let second = sequences
+            .press(
+                control(rdev::Key::KeyS),
+                start + Duration::from_millis(500),
+                never,
+            )
+            .unwrap();
+        assert!(second.extended);
+        assert_eq!(second.name(), "C-x C-s");
+
+        // too late to continue it
+        let third = sequences
+            .press(
+                control(rdev::Key::KeyF),
+                start + Duration::from_secs(3),
+                never,
+            )
+            .unwrap();
+        assert!(!third.extended);
+    }
+
+    #[test]
+    fn plain_keys_only_continue_prefixes() {
+        let mut sequences = Sequences::new(&SequenceConfig::default()).unwrap();
+        let now = Instant::now();
+        let never = |_: &[Hotkey]| false;
+
+        sequences.press(chord(&[Modifier::Control], rdev::Key::KeyX), now, never);
+
+        let buffer = sequences
+            .press(chord(&[], rdev::Key::KeyB), now, never)
+            .unwrap();
+        assert_eq!(buffer.name(), "C-x b");
+
+        // "C-x b" is done, so the next plain key is just a key
+        assert!(sequences
+            .press(chord(&[], rdev::Key::KeyB), now, never)
+            .is_none());
+
+        sequences.press(chord(&[Modifier::Alt], rdev::Key::KeyQ), now, never);
+        assert!(sequences
+            .press(chord(&[], rdev::Key::KeyB), now, never)
+            .is_none());
+    }
+}
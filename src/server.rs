@@ -0,0 +1,543 @@
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{IpAddr, SocketAddr, TcpListener, TcpStream},
+    sync::{mpsc, Arc, Mutex},
+    time::Duration,
+};
+
+use iced::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::{history::KeyEntry, labels::Labels, theme::Theme};
+
+const PAGE: &str = include_str!("../assets/overlay.html");
+
+const EMPTY_STATE: &str = r#"{"entries":[],"truncated":false,"message":null}"#;
+
+/// A page that takes longer to read an event is dropped, so it can't hold up the others
+const CLIENT_WRITE_TIMEOUT: Duration = Duration::from_millis(250);
+/// How long a connection has to send its request
+const CLIENT_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ServerConfig {
+    pub enabled: Option<bool>,
+    pub address: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Some(false),
+            address: Some("127.0.0.1:8910".to_string()),
+        }
+    }
+}
+
+/// How the overlay page looks, taken from the config
+pub struct PageStyle {
+    pub font_size: u32,
+    pub width: u32,
+    pub theme: Theme,
+    /// The configured font and its fallbacks
+    pub fonts: Vec<&'static [u8]>,
+}
+
+impl PageStyle {
+    /// `version` changes with the style, so the browser doesn't keep old fonts
+    fn css(&self, version: u32) -> String {
+        let caps = &self.theme.caps;
+
+        // the browser falls back per glyph, like the window does per key
+        let font_faces: String = (0..self.fonts.len())
+            .map(|i| {
+                format!(
+                    "@font-face {{\n  font-family: \"Font {i}\";\n  src: url(\"/fonts/{i}.ttf?{version}\");\n}}\n\n"
+                )
+            })
+            .collect();
+
+        let font_family: String = (0..self.fonts.len())
+            .map(|i| format!("\"Font {i}\", "))
+            .collect();
+
+        format!(
+            r#"{font_faces}html, body {{
+  margin: 0;
+  background: transparent;
+  overflow: hidden;
+}}
+
+#keys {{
+  box-sizing: border-box;
+  max-width: {width}px;
+  height: {height}px;
+  padding: 0 10px;
+  display: flex;
+  align-items: center;
+  gap: {spacing}em;
+  white-space: pre;
+  font-family: {font_family}monospace;
+  font-size: {font_size}px;
+  color: {text};
+  background: {background};
+  border-radius: {background_radius}px;
+}}
+
+.key {{
+  display: flex;
+  align-items: center;
+  gap: {padding}em;
+  padding: {padding}em;
+  line-height: 1;
+  color: {text};
+  border: {border_width}px solid {border};
+  border-radius: {radius}em;
+  background: {key};
+  box-shadow: {shadow_x}em {shadow_y}em 0 {shadow};
+}}
+
+.key.modifier {{
+  color: {modifier_text};
+  border-color: {modifier};
+  background: {modifier_key};
+}}
+
+.key.chord,
+.key.command {{
+  color: {chord_text};
+  border-color: {chord};
+  background: {chord_key};
+}}
+
+.counter {{
+  padding: 0.1em 0.3em;
+  font-size: 0.6em;
+  color: {counter_text};
+  background: {counter};
+  border-radius: 1em;
+}}
+
+#keys.empty {{
+  visibility: hidden;
+}}
+"#,
+            width = self.width,
+            height = caps.height(self.font_size) + 10,
+            font_size = self.font_size,
+            spacing = caps.spacing,
+            text = css_color(self.theme.text),
+            background = css_color(self.theme.background),
+            background_radius = self.theme.background_radius,
+            padding = caps.padding,
+            border_width = caps.border_width,
+            border = css_color(caps.key.border_color),
+            radius = caps.border_radius,
+            key = css_color(caps.key.background),
+            shadow_x = caps.shadow_offset.x,
+            shadow_y = caps.shadow_offset.y,
+            shadow = css_color(caps.shadow_color),
+            modifier_text = css_color(caps.modifier.text_color),
+            modifier = css_color(caps.modifier.border_color),
+            modifier_key = css_color(caps.modifier.background),
+            chord_text = css_color(caps.chord.text_color),
+            chord = css_color(caps.chord.border_color),
+            chord_key = css_color(caps.chord.background),
+            counter_text = css_color(caps.counter.text_color),
+            counter = css_color(caps.counter.background),
+        )
+    }
+}
+
+fn css_color(color: Color) -> String {
+    let channel = |value: f32| (value * 255.0).round() as u8;
+
+    format!(
+        "rgba({}, {}, {}, {:.2})",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        color.a
+    )
+}
+
+/// What the page gets on every change
+#[derive(Serialize)]
+struct State<'a> {
+    entries: Vec<PageEntry<'a>>,
+    truncated: bool,
+    message: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct PageEntry<'a> {
+    label: String,
+    #[serde(flatten)]
+    entry: &'a KeyEntry,
+}
+
+/// The stylesheet and fonts the page loads
+struct Assets {
+    css: String,
+    fonts: Vec<&'static [u8]>,
+    version: u32,
+}
+
+/// What the event writer gets
+enum Update {
+    /// A page that connected to the events
+    Client(TcpStream),
+    State(String),
+    /// The page loads its style again
+    Style(u32),
+}
+
+/// Serves the overlay as a web page for OBS browser sources,
+/// and pushes the key history to it with server-sent events
+pub struct Server {
+    updates: mpsc::Sender<Update>,
+    assets: Arc<Mutex<Assets>>,
+}
+
+impl Server {
+    pub fn spawn(address: &str, style: PageStyle) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+
+        let (updates, received) = mpsc::channel();
+
+        let assets = Arc::new(Mutex::new(Assets {
+            css: style.css(0),
+            fonts: style.fonts,
+            version: 0,
+        }));
+
+        std::thread::spawn(move || write_events(received));
+
+        {
+            let updates = updates.clone();
+            let assets = assets.clone();
+
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let updates = updates.clone();
+                    let assets = assets.clone();
+
+                    std::thread::spawn(move || {
+                        if let Err(e) = handle(stream, &assets, &updates) {
+                            eprintln!("Overlay server connection failed: {e}");
+                        }
+                    });
+                }
+            });
+        }
+
+        Ok(Self { updates, assets })
+    }
+
+    /// Changes the look of the page, open pages load it again
+    pub fn set_style(&self, style: PageStyle) {
+        let mut assets = self.assets.lock().unwrap();
+
+        // the same style again, like after a reload that changed something else
+        if style.css(assets.version) == assets.css && style.fonts == assets.fonts {
+            return;
+        }
+
+        let version = assets.version + 1;
+
+        *assets = Assets {
+            css: style.css(version),
+            fonts: style.fonts,
+            version,
+        };
+
+        let _ = self.updates.send(Update::Style(version));
+    }
+
+    pub fn publish(
+        &self,
+        entries: &[&KeyEntry],
+        labels: &Labels,
+        truncated: bool,
+        message: Option<&str>,
+    ) {
+        let state = State {
+            entries: entries
+                .iter()
+                .map(|entry| PageEntry {
+                    label: entry.label(labels),
+                    entry,
+                })
+                .collect(),
+            truncated,
+            message,
+        };
+
+        if let Ok(state) = serde_json::to_string(&state) {
+            let _ = self.updates.send(Update::State(state));
+        }
+    }
+}
+
+/// Sends the events to the pages, off the UI thread
+fn write_events(updates: mpsc::Receiver<Update>) {
+    let mut clients: Vec<TcpStream> = Vec::new();
+    let mut last_state = EMPTY_STATE.to_string();
+
+    while let Ok(update) = updates.recv() {
+        let event = match update {
+            Update::Client(mut client) => {
+                // a new page starts with the current state
+                if client
+                    .write_all(frame(None, &last_state).as_bytes())
+                    .is_ok()
+                {
+                    clients.push(client);
+                }
+                continue;
+            }
+            Update::State(state) => {
+                let event = frame(None, &state);
+                last_state = state;
+                event
+            }
+            Update::Style(version) => frame(Some("style"), &version.to_string()),
+        };
+
+        // clients that can't be written to in time are gone or too slow
+        clients.retain_mut(|client| client.write_all(event.as_bytes()).is_ok());
+    }
+}
+
+/// A server-sent event, every line of `data` is sent as one data field
+fn frame(event: Option<&str>, data: &str) -> String {
+    let mut frame = String::new();
+
+    if let Some(event) = event {
+        frame.push_str(&format!("event: {event}\n"));
+    }
+
+    for line in data.lines() {
+        frame.push_str(&format!("data: {line}\n"));
+    }
+
+    frame.push('\n');
+    frame
+}
+
+/// Only names of this machine, so pages of other sites can't read the keys
+/// by pointing their own name at it
+fn is_allowed_host(host: &str, local: SocketAddr) -> bool {
+    // "localhost:8910" or "[::1]:8910"
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if port.parse::<u16>().is_ok() => name,
+        _ => host,
+    };
+    let name = name.trim_start_matches('[').trim_end_matches(']');
+
+    name.eq_ignore_ascii_case("localhost")
+        || name == "127.0.0.1"
+        || name.parse::<IpAddr>().is_ok_and(|ip| ip == local.ip())
+}
+
+fn handle(
+    mut stream: TcpStream,
+    assets: &Mutex<Assets>,
+    updates: &mpsc::Sender<Update>,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(CLIENT_READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(CLIENT_WRITE_TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // only the host is needed of the headers
+    let mut host = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_string());
+            }
+        }
+
+        header.clear();
+    }
+
+    let local = stream.local_addr()?;
+
+    if !host.is_some_and(|host| is_allowed_host(&host, local)) {
+        return respond(&mut stream, "403 Forbidden", "text/plain", b"Forbidden");
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+
+    if method != Some("GET") {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"");
+    }
+
+    // the version query only keeps browsers from caching old styles
+    let path = path.map(|path| path.split('?').next().unwrap_or(path));
+
+    match path {
+        Some("/") => respond(
+            &mut stream,
+            "200 OK",
+            "text/html; charset=utf-8",
+            PAGE.as_bytes(),
+        ),
+        Some("/style.css") => {
+            let css = assets.lock().unwrap().css.clone();
+            respond(&mut stream, "200 OK", "text/css", css.as_bytes())
+        }
+        Some(path) if path.starts_with("/fonts/") => {
+            let font = path
+                .strip_prefix("/fonts/")
+                .and_then(|name| name.strip_suffix(".ttf"))
+                .and_then(|index| index.parse::<usize>().ok())
+                .and_then(|index| assets.lock().unwrap().fonts.get(index).copied());
+
+            match font {
+                Some(font) => respond(&mut stream, "200 OK", "font/ttf", font),
+                None => respond(&mut stream, "404 Not Found", "text/plain", b"Not Found"),
+            }
+        }
+        Some("/events") => {
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n"
+            )?;
+
+            // the writer sends it the current state first, so no update is missed
+            let _ = updates.send(Update::Client(stream));
+            Ok(())
+        }
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"Not Found"),
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &[u8],
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    stream.write_all(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    /// The response to `request`, and what the server passed on to the event writer
+    fn request(request: &str) -> (String, Option<Update>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (stream, _) = listener.accept().unwrap();
+
+        client.write_all(request.as_bytes()).unwrap();
+
+        let assets = Mutex::new(Assets {
+            css: "#keys {}".to_string(),
+            fonts: Vec::new(),
+            version: 0,
+        });
+        let (updates, received) = mpsc::channel();
+
+        handle(stream, &assets, &updates).unwrap();
+        let update = received.try_recv().ok();
+
+        // an events stream stays open, so only what's written so far
+        client
+            .set_read_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        let mut response = Vec::new();
+        let _ = client.read_to_end(&mut response);
+
+        (String::from_utf8(response).unwrap(), update)
+    }
+
+    fn get(path: &str, host: &str) -> (String, Option<Update>) {
+        request(&format!("GET {path} HTTP/1.1\r\nHost: {host}\r\n\r\n"))
+    }
+
+    #[test]
+    fn the_page_and_its_style_are_served() {
+        let (page, _) = get("/", "127.0.0.1:8910");
+        assert!(page.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(page.contains("text/html"));
+
+        let (style, _) = get("/style.css?3", "localhost:8910");
+        assert!(style.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(style.ends_with("#keys {}"));
+    }
+
+    #[test]
+    fn events_go_to_the_writer() {
+        let (response, update) = get("/events", "localhost");
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("Content-Type: text/event-stream\r\n"));
+        assert!(matches!(update, Some(Update::Client(_))));
+    }
+
+    #[test]
+    fn unknown_paths_are_not_found() {
+        for path in ["/nope", "/fonts/0.ttf", "/fonts/x.ttf"] {
+            let (response, update) = get(path, "localhost");
+
+            assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"), "{path}");
+            assert!(update.is_none());
+        }
+
+        let (response, _) = request("POST / HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+
+    #[test]
+    fn other_hosts_are_refused() {
+        for host in ["evil.example", "evil.example:8910", "127.0.0.2"] {
+            let (response, update) = get("/events", host);
+
+            assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"), "{host}");
+            assert!(update.is_none());
+        }
+
+        let (response, _) = request("GET /events HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden\r\n"));
+    }
+
+    #[test]
+    fn hosts_of_this_machine_are_allowed() {
+        let local = "192.168.1.5:8910".parse().unwrap();
+
+        assert!(is_allowed_host("localhost", local));
+        assert!(is_allowed_host("LocalHost:8910", local));
+        assert!(is_allowed_host("127.0.0.1:8910", local));
+        assert!(is_allowed_host("192.168.1.5:8910", local));
+        assert!(is_allowed_host("[::1]:8910", "[::1]:8910".parse().unwrap()));
+
+        assert!(!is_allowed_host("192.168.1.6:8910", local));
+        assert!(!is_allowed_host("localhost.evil.example", local));
+    }
+
+    #[test]
+    fn events_are_framed() {
+        assert_eq!(
+            frame(None, r#"{"entries":[]}"#),
+            "data: {\"entries\":[]}\n\n"
+        );
+        assert_eq!(frame(Some("style"), "2"), "event: style\ndata: 2\n\n");
+        assert_eq!(frame(None, "one\ntwo"), "data: one\ndata: two\n\n");
+    }
+}
@@ -0,0 +1,46 @@
+use iced::Subscription;
+use iced_native::subscription;
+
+pub enum State {
+    Waiting,
+    Finished,
+}
+
+/// Fires once when the app is asked to stop with Ctrl+C, or SIGTERM on unix
+pub fn listen() -> Subscription<()> {
+    struct Signals;
+
+    subscription::unfold(
+        std::any::TypeId::of::<Signals>(),
+        State::Waiting,
+        |state| async move {
+            match state {
+                State::Waiting => match wait().await {
+                    Ok(()) => (Some(()), State::Finished),
+                    Err(e) => {
+                        eprintln!("Could not listen for exit signals: {e}");
+                        (None, State::Finished)
+                    }
+                },
+                State::Finished => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+#[cfg(unix)]
+async fn wait() -> std::io::Result<()> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = signal(SignalKind::terminate())?;
+
+    tokio::select! {
+        interrupted = tokio::signal::ctrl_c() => interrupted,
+        _ = terminate.recv() => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait() -> std::io::Result<()> {
+    tokio::signal::ctrl_c().await
+}
@@ -0,0 +1,200 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime},
+};
+
+use serde::Deserialize;
+
+/// The badge is drawn smaller than the keys
+pub const BADGE_SCALE: f32 = 0.6;
+
+/// Speeds over less time than this jump around too much
+const MIN_SPAN: Duration = Duration::from_secs(5);
+
+/// Characters per word, as typing tests count them
+const WORD_LENGTH: f64 = 5.0;
+
+/// The `[speed]` section
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpeedConfig {
+    pub enabled: Option<bool>,
+    /// Seconds the speeds are averaged over
+    pub window: Option<u64>,
+}
+
+impl Default for SpeedConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Some(false),
+            window: Some(60),
+        }
+    }
+}
+
+impl SpeedConfig {
+    pub fn window(&self) -> Duration {
+        Duration::from_secs(
+            self.window
+                .unwrap_or_else(|| SpeedConfig::default().window.unwrap())
+                .max(1),
+        )
+    }
+}
+
+/// When the recent key presses and clicks happened, for words and actions per minute
+#[derive(Debug, Clone, Default)]
+pub struct Speed {
+    window: Duration,
+    /// Typed characters
+    characters: VecDeque<SystemTime>,
+    /// Every key press and click
+    actions: VecDeque<SystemTime>,
+    started: Option<SystemTime>,
+}
+
+impl Speed {
+    /// Sets how long the speeds are averaged over
+    pub fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+
+    /// A key pressed at `time`, `typed` if it typed a character
+    pub fn press(&mut self, time: SystemTime, typed: bool) {
+        if typed {
+            self.characters.push_back(time);
+        }
+
+        self.action(time);
+    }
+
+    /// A mouse click at `time`
+    pub fn click(&mut self, time: SystemTime) {
+        self.action(time);
+    }
+
+    fn action(&mut self, time: SystemTime) {
+        self.started.get_or_insert(time);
+        self.actions.push_back(time);
+
+        self.forget(time);
+    }
+
+    /// Drops what happened before the window
+    fn forget(&mut self, now: SystemTime) {
+        while self
+            .characters
+            .front()
+            .is_some_and(|time| self.is_old(time, now))
+        {
+            self.characters.pop_front();
+        }
+
+        while self
+            .actions
+            .front()
+            .is_some_and(|time| self.is_old(time, now))
+        {
+            self.actions.pop_front();
+        }
+    }
+
+    fn is_old(&self, time: &SystemTime, now: SystemTime) -> bool {
+        now.duration_since(*time).unwrap_or_default() > self.window
+    }
+
+    fn recent(&self, times: &VecDeque<SystemTime>, now: SystemTime) -> usize {
+        times.iter().filter(|time| !self.is_old(time, now)).count()
+    }
+
+    /// Minutes of the window that passed, shorter at the start of the session
+    fn minutes(&self, now: SystemTime) -> f64 {
+        let since_start = self
+            .started
+            .map(|started| now.duration_since(started).unwrap_or_default())
+            .unwrap_or_default();
+
+        since_start
+            .clamp(MIN_SPAN, self.window.max(MIN_SPAN))
+            .as_secs_f64()
+            / 60.0
+    }
+
+    /// Words per minute, counting 5 characters as a word
+    pub fn wpm(&self, now: SystemTime) -> u32 {
+        (self.recent(&self.characters, now) as f64 / WORD_LENGTH / self.minutes(now)).round() as u32
+    }
+
+    /// Key presses and clicks per minute
+    pub fn apm(&self, now: SystemTime) -> u32 {
+        (self.recent(&self.actions, now) as f64 / self.minutes(now)).round() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn speed(window: u64) -> (Speed, impl Fn(u64) -> SystemTime) {
+        let mut speed = Speed::default();
+        speed.set_window(Duration::from_secs(window));
+
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        (speed, move |millis| start + Duration::from_millis(millis))
+    }
+
+    #[test]
+    fn rates_are_per_minute() {
+        let (mut speed, at) = speed(60);
+
+        // 50 characters in 10 seconds
+        for i in 0..50 {
+            speed.press(at(i * 200), true);
+        }
+
+        assert_eq!(speed.wpm(at(10_000)), 60);
+        assert_eq!(speed.apm(at(10_000)), 300);
+    }
+
+    #[test]
+    fn clicks_and_shortcuts_are_actions_only() {
+        let (mut speed, at) = speed(60);
+
+        speed.press(at(0), true);
+        speed.press(at(1000), false);
+        speed.click(at(2000));
+
+        assert_eq!(speed.characters.len(), 1);
+        assert_eq!(speed.actions.len(), 3);
+    }
+
+    #[test]
+    fn a_short_burst_is_spread_over_the_minimum_span() {
+        let (mut speed, at) = speed(60);
+
+        for i in 0..5 {
+            speed.press(at(i * 100), true);
+        }
+
+        // as if the 5 characters took 5 seconds, not half of one
+        assert_eq!(speed.wpm(at(1000)), 12);
+        assert_eq!(speed.apm(at(1000)), 60);
+    }
+
+    #[test]
+    fn presses_before_the_window_are_dropped() {
+        let (mut speed, at) = speed(10);
+
+        speed.press(at(0), true);
+        speed.press(at(5_000), true);
+        speed.press(at(30_000), true);
+
+        assert_eq!(speed.characters.len(), 1);
+        assert_eq!(speed.actions.len(), 1);
+        assert_eq!(speed.apm(at(30_000)), 6);
+
+        // nothing typed for longer than the window
+        assert_eq!(speed.wpm(at(60_000)), 0);
+        assert_eq!(speed.apm(at(60_000)), 0);
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::key_caps::KeyCap;
+
+/// How the entries are laid out in the window
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    /// One line, dropping the oldest keys when it's full
+    #[default]
+    Strip,
+    /// A line per burst of typing, stacked
+    Stack,
+}
+
+/// The `[stack]` section, used with `layout = "stack"`
+#[derive(Debug, Clone, Deserialize)]
+pub struct StackConfig {
+    /// How many lines are shown
+    pub lines: Option<usize>,
+    /// Milliseconds without a key press that start a new line
+    pub pause: Option<u64>,
+    /// Older lines get smaller and more transparent
+    pub fade: Option<bool>,
+}
+
+impl Default for StackConfig {
+    fn default() -> Self {
+        Self {
+            lines: Some(3),
+            pause: Some(1000),
+            fade: Some(true),
+        }
+    }
+}
+
+impl StackConfig {
+    pub fn lines(&self) -> usize {
+        self.lines
+            .unwrap_or_else(|| StackConfig::default().lines.unwrap())
+            .max(1)
+    }
+
+    pub fn pause(&self) -> Duration {
+        Duration::from_millis(
+            self.pause
+                .unwrap_or_else(|| StackConfig::default().pause.unwrap()),
+        )
+    }
+
+    /// The scale and opacity of a line, `age` is 0 for the newest one
+    pub fn fade(&self, age: usize) -> (f32, f32) {
+        if !self
+            .fade
+            .unwrap_or_else(|| StackConfig::default().fade.unwrap())
+        {
+            return (1.0, 1.0);
+        }
+
+        let age = age as f32;
+
+        ((1.0 - age * 0.1).max(0.6), (1.0 - age * 0.25).max(0.25))
+    }
+}
+
+/// A line of caps as it's drawn
+pub struct Line {
+    pub caps: Vec<KeyCap>,
+    pub size: u16,
+    pub opacity: f32,
+    /// Starts with the mark for dropped keys
+    pub truncated: bool,
+}
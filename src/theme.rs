@@ -0,0 +1,404 @@
+use std::{collections::HashMap, str::FromStr};
+
+use iced::{Color, Vector};
+use serde::Deserialize;
+
+use crate::key_caps::{CapStyle, Style};
+
+/// Built-in themes, users can add more in `[themes.<name>]`
+pub const BUILT_IN: [&str; 4] = ["dark", "light", "high-contrast", "solarized"];
+
+/// A color written as "#rgb", "#rrggbb" or "#rrggbbaa"
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Hex(pub Color);
+
+impl FromStr for Hex {
+    type Err = String;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        let error = || format!("\"{hex}\" is not a \"#rgb\", \"#rrggbb\" or \"#rrggbbaa\" color");
+
+        let digits = hex
+            .strip_prefix('#')
+            .filter(|digits| matches!(digits.len(), 3 | 6 | 8) && digits.is_ascii())
+            .ok_or_else(error)?;
+
+        // "#f80" is "#ff8800"
+        let digits = match digits.len() {
+            3 => digits.chars().flat_map(|digit| [digit, digit]).collect(),
+            _ => digits.to_string(),
+        };
+
+        let channels = (0..digits.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&digits[i..i + 2], 16))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| error())?;
+
+        Ok(Hex(Color::from_rgba8(
+            channels[0],
+            channels[1],
+            channels[2],
+            channels
+                .get(3)
+                .map_or(1.0, |alpha| f32::from(*alpha) / 255.0),
+        )))
+    }
+}
+
+impl TryFrom<String> for Hex {
+    type Error = String;
+
+    fn try_from(hex: String) -> Result<Self, Self::Error> {
+        hex.parse()
+    }
+}
+
+/// Everything a theme can set, unset values come from the theme it's based on
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThemeValues {
+    pub background: Option<Hex>,
+    /// Multiplies the alpha of the background
+    pub opacity: Option<f32>,
+    /// Corner radius of the overlay in pixels
+    pub background_radius: Option<f32>,
+    pub text: Option<Hex>,
+    /// Fill of the key-caps
+    pub key: Option<Hex>,
+    pub border: Option<Hex>,
+    pub border_width: Option<f32>,
+    /// Corner radius of the key-caps, relative to the font size
+    pub radius: Option<f32>,
+    /// Space around the key labels, relative to the font size
+    pub padding: Option<f32>,
+    pub shadow: Option<Hex>,
+    /// Accent of modifier caps
+    pub modifier: Option<Hex>,
+    /// Accent of chord caps
+    pub chord: Option<Hex>,
+    /// Accent of repeat counters
+    pub counter: Option<Hex>,
+}
+
+impl ThemeValues {
+    /// Takes the values set in `other`, keeping the rest
+    fn merge(self, other: &ThemeValues) -> ThemeValues {
+        ThemeValues {
+            background: other.background.or(self.background),
+            opacity: other.opacity.or(self.opacity),
+            background_radius: other.background_radius.or(self.background_radius),
+            text: other.text.or(self.text),
+            key: other.key.or(self.key),
+            border: other.border.or(self.border),
+            border_width: other.border_width.or(self.border_width),
+            radius: other.radius.or(self.radius),
+            padding: other.padding.or(self.padding),
+            shadow: other.shadow.or(self.shadow),
+            modifier: other.modifier.or(self.modifier),
+            chord: other.chord.or(self.chord),
+            counter: other.counter.or(self.counter),
+        }
+    }
+
+    fn built_in(name: &str) -> Option<ThemeValues> {
+        let hex = |hex: &str| Some(hex.parse::<Hex>().unwrap());
+
+        let dark = ThemeValues {
+            background: hex("#000000"),
+            opacity: Some(0.5),
+            background_radius: Some(10.),
+            text: hex("#ffffff"),
+            key: hex("#262626e6"),
+            border: hex("#ffffff4d"),
+            border_width: Some(1.),
+            radius: Some(0.2),
+            padding: Some(0.3),
+            shadow: hex("#00000080"),
+            modifier: hex("#ccccee"),
+            chord: hex("#598ce6"),
+            counter: hex("#e68c1a"),
+        };
+
+        match name {
+            "dark" => Some(dark),
+            "light" => Some(ThemeValues {
+                background: hex("#ffffff"),
+                opacity: Some(0.7),
+                text: hex("#1e1e1e"),
+                key: hex("#f5f5f5f2"),
+                border: hex("#0000004d"),
+                shadow: hex("#00000040"),
+                modifier: hex("#505a78"),
+                chord: hex("#2b6cd4"),
+                ..dark
+            }),
+            "high-contrast" => Some(ThemeValues {
+                opacity: Some(0.9),
+                key: hex("#000000"),
+                border: hex("#ffffff"),
+                border_width: Some(2.),
+                shadow: hex("#00000000"),
+                modifier: hex("#ffff00"),
+                chord: hex("#00ffff"),
+                counter: hex("#ff00ff"),
+                ..dark
+            }),
+            "solarized" => Some(ThemeValues {
+                background: hex("#002b36"),
+                opacity: Some(0.85),
+                text: hex("#93a1a1"),
+                key: hex("#073642"),
+                border: hex("#586e75"),
+                shadow: hex("#00000066"),
+                modifier: hex("#b58900"),
+                chord: hex("#268bd2"),
+                counter: hex("#cb4b16"),
+                ..dark
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The `[theme]` section, a theme name with values to change in it
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThemeConfig {
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub values: ThemeValues,
+}
+
+/// A `[themes.<name>]` section
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserTheme {
+    /// The theme this one changes, "dark" if not set
+    pub base: Option<String>,
+    #[serde(flatten)]
+    pub values: ThemeValues,
+}
+
+/// How the overlay looks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub background: Color,
+    pub background_radius: f32,
+    pub text: Color,
+    pub caps: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::from_values(&ThemeValues::built_in("dark").unwrap())
+    }
+}
+
+impl Theme {
+    pub fn resolve(
+        config: &ThemeConfig,
+        themes: &HashMap<String, UserTheme>,
+    ) -> Result<Theme, String> {
+        let name = config.name.as_deref().unwrap_or("dark");
+
+        let values = values_of(name, themes, &[])?.merge(&config.values);
+
+        Ok(Theme::from_values(&values))
+    }
+
+    /// Expects every value to be set, which is the case for anything based on a built-in theme
+    fn from_values(values: &ThemeValues) -> Theme {
+        let color = |hex: Option<Hex>| hex.map(|Hex(color)| color).unwrap_or(Color::BLACK);
+
+        let mut background = color(values.background);
+        background.a *= values.opacity.unwrap_or(1.);
+
+        let text = color(values.text);
+        let key = color(values.key);
+
+        Theme {
+            background,
+            background_radius: values.background_radius.unwrap_or_default(),
+            text,
+            caps: Style {
+                key: CapStyle {
+                    background: key,
+                    border_color: color(values.border),
+                    text_color: text,
+                },
+                modifier: CapStyle {
+                    background: key,
+                    border_color: color(values.modifier),
+                    text_color: color(values.modifier),
+                },
+                chord: CapStyle {
+                    background: key,
+                    border_color: color(values.chord),
+                    text_color: text,
+                },
+                counter: CapStyle {
+                    background: color(values.counter),
+                    border_color: Color::TRANSPARENT,
+                    text_color: Color { a: 1., ..key },
+                },
+                border_radius: values.radius.unwrap_or_default(),
+                border_width: values.border_width.unwrap_or_default(),
+                padding: values.padding.unwrap_or_default(),
+                spacing: 0.2,
+                shadow_offset: Vector::new(0.0, 0.08),
+                shadow_color: color(values.shadow),
+            },
+        }
+    }
+}
+
+/// The values of a theme over the ones of its bases, `based` are the themes that led to it
+fn values_of(
+    name: &str,
+    themes: &HashMap<String, UserTheme>,
+    based: &[&str],
+) -> Result<ThemeValues, String> {
+    if let Some(start) = based.iter().position(|based| *based == name) {
+        let mut cycle = based[start..].to_vec();
+        cycle.push(name);
+
+        return Err(format!(
+            "themes are based on each other: {}",
+            cycle.join(" -> ")
+        ));
+    }
+
+    // user themes can replace built-in ones, but be based on them too
+    if let Some(theme) = themes.get(name) {
+        let base = theme.base.as_deref().unwrap_or("dark");
+
+        let base = if base == name {
+            ThemeValues::built_in(base)
+                .ok_or_else(|| format!("theme \"{name}\" is based on itself"))?
+        } else {
+            values_of(base, themes, &[based, &[name]].concat())?
+        };
+
+        return Ok(base.merge(&theme.values));
+    }
+
+    ThemeValues::built_in(name).ok_or_else(|| {
+        let mut names: Vec<_> = BUILT_IN.iter().map(ToString::to_string).collect();
+        names.extend(themes.keys().cloned());
+
+        format!("unknown theme \"{name}\", use one of: {}", names.join(", "))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(hex: &str) -> Color {
+        hex.parse::<Hex>().unwrap().0
+    }
+
+    fn themes(source: &str) -> HashMap<String, UserTheme> {
+        toml::from_str(source).unwrap()
+    }
+
+    fn resolve(name: &str, themes: &HashMap<String, UserTheme>) -> Result<Theme, String> {
+        let config = ThemeConfig {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+
+        Theme::resolve(&config, themes)
+    }
+
+    #[test]
+    fn hex_colors_are_parsed() {
+        assert_eq!(hex("#ff8800"), Color::from_rgb8(255, 136, 0));
+        assert_eq!(hex("#FF8800"), hex("#ff8800"));
+        assert_eq!(hex("#f80"), hex("#ff8800"));
+        assert_eq!(
+            hex("#ff880080"),
+            Color::from_rgba8(255, 136, 0, 128.0 / 255.0)
+        );
+
+        for bad in ["ff8800", "#ff88", "#gg8800", "#ff8800ff00", "#ééé", ""] {
+            assert!(bad.parse::<Hex>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn themes_change_their_base() {
+        let themes = themes(
+            r##"
+            [mine]
+            base = "warm"
+            key = "#abc"
+
+            [warm]
+            base = "light"
+            text = "#123"
+            "##,
+        );
+
+        let light = resolve("light", &themes).unwrap();
+        let mine = resolve("mine", &themes).unwrap();
+
+        assert_eq!(mine.caps.key.background, hex("#abc"));
+        assert_eq!(mine.text, hex("#123"));
+        assert_eq!(mine.background, light.background);
+    }
+
+    #[test]
+    fn built_in_themes_can_be_replaced() {
+        let themes = themes(
+            r##"
+            [dark]
+            base = "dark"
+            text = "#f00"
+            "##,
+        );
+
+        let dark = resolve("dark", &themes).unwrap();
+        let built_in = resolve("dark", &HashMap::new()).unwrap();
+
+        assert_eq!(dark.text, hex("#f00"));
+        assert_eq!(dark.background, built_in.background);
+    }
+
+    #[test]
+    fn cycles_are_named() {
+        let pair = themes("[a]\nbase = \"b\"\n[b]\nbase = \"a\"");
+
+        assert_eq!(
+            resolve("a", &pair).err().as_deref(),
+            Some("themes are based on each other: a -> b -> a")
+        );
+
+        let themes = themes(
+            r#"
+            [a]
+            base = "b"
+
+            [b]
+            base = "c"
+
+            [c]
+            base = "b"
+            "#,
+        );
+
+        assert_eq!(
+            resolve("a", &themes).err().as_deref(),
+            Some("themes are based on each other: b -> c -> b")
+        );
+    }
+
+    #[test]
+    fn unknown_themes_are_refused() {
+        assert!(resolve("nope", &HashMap::new())
+            .unwrap_err()
+            .starts_with("unknown theme \"nope\""));
+
+        let themes = themes("[mine]\nbase = \"nope\"");
+        assert!(resolve("mine", &themes).is_err());
+    }
+}
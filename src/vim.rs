@@ -0,0 +1,881 @@
+use std::collections::BTreeSet;
+
+use serde::Deserialize;
+
+use crate::keys::{Keys, Modifier};
+
+/// The `[vim]` section
+#[derive(Debug, Clone, Deserialize)]
+pub struct VimConfig {
+    pub enabled: Option<bool>,
+    /// Window classes, or words in the window title, that mean Vim is focused
+    pub apps: Option<Vec<String>>,
+    /// Show what the commands do under the keys
+    pub descriptions: Option<bool>,
+}
+
+impl Default for VimConfig {
+    fn default() -> Self {
+        Self {
+            enabled: Some(false),
+            apps: Some(
+                ["vim", "nvim", "gvim", "neovide"]
+                    .map(String::from)
+                    .to_vec(),
+            ),
+            descriptions: Some(true),
+        }
+    }
+}
+
+/// Vim's mode, as far as it can be guessed from the keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum VimMode {
+    #[default]
+    Normal,
+    Insert,
+    Visual,
+    VisualLine,
+    /// After ":", "/" or "?", until Enter
+    CommandLine,
+}
+
+/// What to show for a key pressed in Vim
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Output {
+    /// The key itself, like Esc or a chord
+    Key,
+    /// Nothing, for text typed in insert mode and the start of a command
+    Hidden,
+    /// A whole command like "ciw" or ":wq"
+    Command {
+        keys: String,
+        description: Option<String>,
+    },
+}
+
+enum Parse {
+    /// More keys are needed
+    Incomplete,
+    /// Not a command this knows, shown as it was typed
+    Unknown,
+    Complete {
+        /// The mode the command switches to
+        enters: Option<VimMode>,
+        description: String,
+    },
+}
+
+impl Parse {
+    fn done(description: impl Into<String>) -> Self {
+        Parse::Complete {
+            enters: None,
+            description: description.into(),
+        }
+    }
+
+    fn enters(mode: VimMode, description: impl Into<String>) -> Self {
+        Parse::Complete {
+            enters: Some(mode),
+            description: description.into(),
+        }
+    }
+}
+
+/// Operators wait for a motion, and act on the line when doubled
+const OPERATORS: &[(&str, &str)] = &[
+    ("g~", "toggle the case of"),
+    ("gu", "lowercase"),
+    ("gU", "uppercase"),
+    ("gq", "format"),
+    ("d", "delete"),
+    ("c", "change"),
+    ("y", "yank"),
+    (">", "indent"),
+    ("<", "unindent"),
+    ("=", "reindent"),
+];
+
+const INSERT_COMMANDS: &[(&str, &str)] = &[
+    ("i", "insert"),
+    ("a", "append"),
+    ("I", "insert at the start of the line"),
+    ("A", "append at the end of the line"),
+    ("o", "open a line below"),
+    ("O", "open a line above"),
+    ("s", "substitute a character"),
+    ("S", "substitute the line"),
+    ("C", "change to the end of the line"),
+    ("gi", "insert where insert mode was left"),
+    ("gI", "insert at the first column"),
+];
+
+const COMMANDS: &[(&str, &str)] = &[
+    ("x", "delete a character"),
+    ("X", "delete the character before"),
+    ("p", "paste after"),
+    ("P", "paste before"),
+    ("u", "undo"),
+    (".", "repeat the last change"),
+    ("J", "join lines"),
+    ("gJ", "join lines without spaces"),
+    ("~", "toggle case"),
+    ("D", "delete to the end of the line"),
+    ("Y", "yank the line"),
+    ("&", "repeat the last substitute"),
+    ("@@", "repeat the last macro"),
+    ("ZZ", "save and quit"),
+    ("ZQ", "quit without saving"),
+    ("zz", "center the line"),
+    ("zt", "scroll the line to the top"),
+    ("zb", "scroll the line to the bottom"),
+    ("zo", "open a fold"),
+    ("zc", "close a fold"),
+    ("za", "toggle a fold"),
+    ("gt", "next tab"),
+    ("gT", "previous tab"),
+    ("gd", "go to the definition"),
+    ("gf", "open the file under the cursor"),
+    ("gx", "open the link under the cursor"),
+];
+
+/// Commands followed by any character, like "ra" or "qq"
+const CHAR_COMMANDS: &[(char, &str)] = &[
+    ('r', "replace with"),
+    ('m', "set mark"),
+    ('q', "record macro"),
+    ('@', "play macro"),
+];
+
+const MOTIONS: &[(&str, &str)] = &[
+    ("h", "left"),
+    ("j", "down"),
+    ("k", "up"),
+    ("l", "right"),
+    ("w", "to the next word"),
+    ("W", "to the next WORD"),
+    ("b", "back a word"),
+    ("B", "back a WORD"),
+    ("e", "to the end of the word"),
+    ("E", "to the end of the WORD"),
+    ("ge", "back to the end of a word"),
+    ("0", "to the start of the line"),
+    ("^", "to the first character of the line"),
+    ("$", "to the end of the line"),
+    ("gg", "to the first line"),
+    ("G", "to the last line"),
+    ("{", "to the previous paragraph"),
+    ("}", "to the next paragraph"),
+    ("(", "to the previous sentence"),
+    (")", "to the next sentence"),
+    ("%", "to the matching bracket"),
+    ("n", "to the next match"),
+    ("N", "to the previous match"),
+    ("*", "to the next match of the word"),
+    ("#", "to the previous match of the word"),
+    ("H", "to the top of the screen"),
+    ("M", "to the middle of the screen"),
+    ("L", "to the bottom of the screen"),
+    (";", "repeat the last f or t"),
+    (",", "repeat the last f or t backwards"),
+];
+
+/// Motions followed by any character, like "fx" or "'a"
+const CHAR_MOTIONS: &[(char, &str)] = &[
+    ('f', "to"),
+    ('F', "back to"),
+    ('t', "till"),
+    ('T', "back till"),
+    ('\'', "to the line of mark"),
+    ('`', "to mark"),
+];
+
+/// What follows "i" (inside) or "a" (around)
+const TEXT_OBJECTS: &[(char, &str)] = &[
+    ('w', "word"),
+    ('W', "WORD"),
+    ('s', "sentence"),
+    ('p', "paragraph"),
+    ('(', "parentheses"),
+    (')', "parentheses"),
+    ('b', "parentheses"),
+    ('[', "brackets"),
+    (']', "brackets"),
+    ('{', "braces"),
+    ('}', "braces"),
+    ('B', "braces"),
+    ('<', "angle brackets"),
+    ('>', "angle brackets"),
+    ('"', "double quotes"),
+    ('\'', "single quotes"),
+    ('`', "backticks"),
+    ('t', "tag"),
+];
+
+const VISUAL_COMMANDS: &[(&str, &str)] = &[
+    ("d", "delete the selection"),
+    ("x", "delete the selection"),
+    ("y", "yank the selection"),
+    ("p", "replace the selection"),
+    (">", "indent the selection"),
+    ("<", "unindent the selection"),
+    ("=", "reindent the selection"),
+    ("~", "toggle the case of the selection"),
+    ("u", "lowercase the selection"),
+    ("U", "uppercase the selection"),
+    ("J", "join the selected lines"),
+    ("gq", "format the selection"),
+];
+
+const COMMAND_LINES: &[(&str, &str)] = &[
+    (":w", "save"),
+    (":q", "quit"),
+    (":q!", "quit without saving"),
+    (":wq", "save and quit"),
+    (":x", "save and quit"),
+    (":wa", "save all"),
+    (":qa", "quit all"),
+    (":qa!", "quit all without saving"),
+    (":wqa", "save all and quit"),
+    (":noh", "clear the search highlight"),
+];
+
+fn find<'a>(table: &[(&str, &'a str)], keys: &str) -> Option<&'a str> {
+    table
+        .iter()
+        .find(|(command, _)| *command == keys)
+        .map(|(_, description)| *description)
+}
+
+fn find_char<'a>(table: &[(char, &'a str)], char: char) -> Option<&'a str> {
+    table
+        .iter()
+        .find(|(command, _)| *command == char)
+        .map(|(_, description)| *description)
+}
+
+/// More keys could still make one of the commands
+fn starts_any(table: &[(&str, &str)], keys: &str) -> bool {
+    table
+        .iter()
+        .any(|(command, _)| command.len() > keys.len() && command.starts_with(keys))
+}
+
+/// Splits a count like the 3 in "3dd" off, 0 alone is a motion
+fn split_count(keys: &str) -> (Option<u32>, &str) {
+    if keys.starts_with('0') {
+        return (None, keys);
+    }
+
+    let digits = keys
+        .find(|char: char| !char.is_ascii_digit())
+        .unwrap_or(keys.len());
+
+    (keys[..digits].parse().ok(), &keys[digits..])
+}
+
+/// Turns keys pressed in Vim into its commands, guessing which mode it's in
+#[derive(Debug, Clone)]
+pub struct Vim {
+    /// Lowercase
+    apps: Vec<String>,
+    descriptions: bool,
+    mode: VimMode,
+    /// Keys of the command typed so far
+    pending: String,
+    /// A macro is being recorded, so "q" ends it
+    recording: bool,
+}
+
+impl Vim {
+    /// `None` unless it's turned on in the config
+    pub fn enabled(config: &VimConfig) -> Option<Self> {
+        config
+            .enabled
+            .unwrap_or_default()
+            .then(|| Self::new(config))
+    }
+
+    pub fn new(config: &VimConfig) -> Self {
+        Self {
+            apps: config
+                .apps
+                .clone()
+                .unwrap_or_else(|| VimConfig::default().apps.unwrap())
+                .iter()
+                .map(|app| app.to_lowercase())
+                .collect(),
+            descriptions: config.descriptions.unwrap_or(true),
+            mode: VimMode::Normal,
+            pending: String::new(),
+            recording: false,
+        }
+    }
+
+    /// Vim is the focused app, by its window class or a word in its title like "NVIM"
+    pub fn is_focused(&self, apps: &[String], title: &str) -> bool {
+        let title = title.to_lowercase();
+
+        apps.iter()
+            .map(|app| app.to_lowercase())
+            .chain(
+                title
+                    .split(|char: char| !char.is_alphanumeric())
+                    .map(String::from),
+            )
+            .any(|name| self.apps.contains(&name))
+    }
+
+    /// Forgets the command typed so far
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+
+    /// A key pressed while holding `held`, `text` is what it typed
+    pub fn press(
+        &mut self,
+        key: rdev::Key,
+        text: Option<&str>,
+        held: &BTreeSet<Modifier>,
+    ) -> Output {
+        if key.modifier().is_some() {
+            return Output::Key;
+        }
+
+        let shortcut = held.iter().any(|modifier| *modifier != Modifier::Shift);
+        let char = (!shortcut).then(|| typed_char(key, text, held)).flatten();
+
+        let escape = key == rdev::Key::Escape
+            || (shortcut
+                && held.contains(&Modifier::Control)
+                && matches!(key, rdev::Key::LeftBracket | rdev::Key::KeyC));
+
+        if escape {
+            self.mode = VimMode::Normal;
+            self.pending.clear();
+            return Output::Key;
+        }
+
+        match self.mode {
+            VimMode::Insert => {
+                let typing = char.is_some()
+                    || matches!(
+                        key,
+                        rdev::Key::Return
+                            | rdev::Key::KpReturn
+                            | rdev::Key::Backspace
+                            | rdev::Key::Tab
+                    );
+
+                if typing && !shortcut {
+                    Output::Hidden
+                } else {
+                    Output::Key
+                }
+            }
+            VimMode::CommandLine => self.command_line(key, char),
+            VimMode::Normal | VimMode::Visual | VimMode::VisualLine => {
+                let Some(char) = char else {
+                    self.pending.clear();
+
+                    // visual block mode
+                    if shortcut && key == rdev::Key::KeyV && self.mode == VimMode::Normal {
+                        self.mode = VimMode::Visual;
+                    }
+
+                    return Output::Key;
+                };
+
+                self.command(char)
+            }
+        }
+    }
+
+    fn command_line(&mut self, key: rdev::Key, char: Option<char>) -> Output {
+        match (key, char) {
+            (rdev::Key::Return | rdev::Key::KpReturn, _) => {
+                self.mode = VimMode::Normal;
+
+                let keys = std::mem::take(&mut self.pending);
+                let description = self.describe(command_line_description(&keys));
+
+                Output::Command { keys, description }
+            }
+            (rdev::Key::Backspace, _) => {
+                self.pending.pop();
+
+                // backspace over the ":" leaves the command line
+                if self.pending.is_empty() {
+                    self.mode = VimMode::Normal;
+                }
+
+                Output::Hidden
+            }
+            (_, Some(char)) => {
+                self.pending.push(char);
+                Output::Hidden
+            }
+            _ => Output::Hidden,
+        }
+    }
+
+    fn command(&mut self, char: char) -> Output {
+        let (_, rest) = split_count(&self.pending);
+
+        // ":" after a count or in visual mode still opens the command line
+        if matches!(char, ':' | '/' | '?') && rest.is_empty() {
+            self.mode = VimMode::CommandLine;
+            self.pending = char.to_string();
+            return Output::Hidden;
+        }
+
+        self.pending.push(char);
+
+        let keys = self.pending.clone();
+
+        match self.parse(&keys) {
+            Parse::Incomplete => Output::Hidden,
+            Parse::Unknown => {
+                self.pending.clear();
+
+                // like a leader key, shown as the key it is
+                if keys.chars().count() == 1 {
+                    Output::Key
+                } else {
+                    Output::Command {
+                        keys,
+                        description: None,
+                    }
+                }
+            }
+            Parse::Complete {
+                enters,
+                description,
+            } => {
+                self.pending.clear();
+
+                if let Some(mode) = enters {
+                    self.mode = mode;
+                }
+
+                Output::Command {
+                    keys,
+                    description: self.describe(Some(description)),
+                }
+            }
+        }
+    }
+
+    fn describe(&self, description: Option<String>) -> Option<String> {
+        description.filter(|_| self.descriptions)
+    }
+
+    /// A count and a register like "a can come before the command
+    fn parse(&mut self, keys: &str) -> Parse {
+        let (count, rest) = split_count(keys);
+
+        let rest = match rest.strip_prefix('"') {
+            Some(register) => {
+                let mut chars = register.chars();
+
+                if chars.next().is_none() {
+                    return Parse::Incomplete;
+                }
+
+                chars.as_str()
+            }
+            None => rest,
+        };
+
+        let (register_count, rest) = split_count(rest);
+
+        if rest.is_empty() {
+            return Parse::Incomplete;
+        }
+
+        let count = count.or(register_count);
+
+        // a count is a line number for these
+        if let (Some(line), "G" | "gg") = (count, rest) {
+            return Parse::done(format!("to line {line}"));
+        }
+
+        let parse = match self.mode {
+            VimMode::Visual | VimMode::VisualLine => self.visual(rest),
+            _ => self.normal(rest),
+        };
+
+        match (parse, count) {
+            (
+                Parse::Complete {
+                    enters,
+                    description,
+                },
+                Some(count),
+            ) => Parse::Complete {
+                enters,
+                description: format!("{description}, {count} times"),
+            },
+            (parse, _) => parse,
+        }
+    }
+
+    fn normal(&mut self, keys: &str) -> Parse {
+        if let Some((operator, name)) = OPERATORS
+            .iter()
+            .find(|(operator, _)| keys.starts_with(operator))
+        {
+            let enters = (*operator == "c").then_some(VimMode::Insert);
+
+            return match operator_motion(operator, name, &keys[operator.len()..]) {
+                Parse::Complete { description, .. } => Parse::Complete {
+                    enters,
+                    description,
+                },
+                parse => parse,
+            };
+        }
+
+        if let Some(description) = find(INSERT_COMMANDS, keys) {
+            return Parse::enters(VimMode::Insert, description);
+        }
+
+        match keys {
+            "v" => return Parse::enters(VimMode::Visual, "visual mode"),
+            "V" => return Parse::enters(VimMode::VisualLine, "visual line mode"),
+            "gv" => return Parse::enters(VimMode::Visual, "reselect the last selection"),
+            "q" if self.recording => {
+                self.recording = false;
+                return Parse::done("stop recording");
+            }
+            _ => {}
+        }
+
+        if let Some(description) = find(COMMANDS, keys) {
+            return Parse::done(description);
+        }
+
+        if starts_any(COMMANDS, keys) || starts_any(INSERT_COMMANDS, keys) {
+            return Parse::Incomplete;
+        }
+
+        let mut chars = keys.chars();
+
+        if let (Some(first), second) = (chars.next(), chars.next()) {
+            if let Some(description) = find_char(CHAR_COMMANDS, first) {
+                let Some(second) = second else {
+                    return Parse::Incomplete;
+                };
+
+                if first == 'q' {
+                    self.recording = true;
+                }
+
+                return Parse::done(format!("{description} {second}"));
+            }
+        }
+
+        motion(keys)
+    }
+
+    fn visual(&mut self, keys: &str) -> Parse {
+        match keys {
+            "v" => {
+                return match self.mode {
+                    VimMode::Visual => Parse::enters(VimMode::Normal, "end visual mode"),
+                    _ => Parse::enters(VimMode::Visual, "visual mode"),
+                }
+            }
+            "V" => {
+                return match self.mode {
+                    VimMode::VisualLine => Parse::enters(VimMode::Normal, "end visual mode"),
+                    _ => Parse::enters(VimMode::VisualLine, "visual line mode"),
+                }
+            }
+            "c" | "s" => return Parse::enters(VimMode::Insert, "change the selection"),
+            "o" => return Parse::done("go to the other end of the selection"),
+            _ => {}
+        }
+
+        if let Some(description) = find(VISUAL_COMMANDS, keys) {
+            return Parse::enters(VimMode::Normal, description);
+        }
+
+        if let Some(object) = keys.strip_prefix(['i', 'a']) {
+            return text_object(keys, object).map_or(Parse::Incomplete, |object| {
+                Parse::done(format!("select {object}"))
+            });
+        }
+
+        if starts_any(VISUAL_COMMANDS, keys) {
+            return Parse::Incomplete;
+        }
+
+        motion(keys)
+    }
+}
+
+/// "iw" as "inside word", `None` while the object is still missing
+fn text_object(keys: &str, object: &str) -> Option<String> {
+    let char = object.chars().next()?;
+
+    let place = if keys.starts_with('i') {
+        "inside"
+    } else {
+        "around"
+    };
+
+    Some(match find_char(TEXT_OBJECTS, char) {
+        Some(name) => format!("{place} {name}"),
+        None => format!("{place} {char}"),
+    })
+}
+
+fn operator_motion(operator: &str, name: &str, keys: &str) -> Parse {
+    let last = &operator[operator.len() - 1..];
+
+    // "dd" and "gUU", or "gUgU"
+    if keys == last || keys == operator {
+        return Parse::done(format!("{name} the line"));
+    }
+
+    if operator.starts_with(keys) {
+        return Parse::Incomplete;
+    }
+
+    let (count, keys) = split_count(keys);
+
+    if keys.is_empty() {
+        return Parse::Incomplete;
+    }
+
+    let parse = match keys.strip_prefix(['i', 'a']) {
+        Some(object) => text_object(keys, object).map_or(Parse::Incomplete, Parse::done),
+        None => motion(keys),
+    };
+
+    match parse {
+        Parse::Complete { description, .. } => Parse::done(match count {
+            Some(count) => format!("{name} {description}, {count} times"),
+            None => format!("{name} {description}"),
+        }),
+        parse => parse,
+    }
+}
+
+fn motion(keys: &str) -> Parse {
+    if let Some(description) = find(MOTIONS, keys) {
+        return Parse::done(description);
+    }
+
+    if starts_any(MOTIONS, keys) {
+        return Parse::Incomplete;
+    }
+
+    let mut chars = keys.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(first), second) => match find_char(CHAR_MOTIONS, first) {
+            Some(description) => second.map_or(Parse::Incomplete, |second| {
+                Parse::done(format!("{description} {second}"))
+            }),
+            None => Parse::Unknown,
+        },
+        (None, _) => Parse::Incomplete,
+    }
+}
+
+fn command_line_description(keys: &str) -> Option<String> {
+    if let Some(description) = find(COMMAND_LINES, keys) {
+        return Some(description.to_string());
+    }
+
+    if let Some(pattern) = keys.strip_prefix('/') {
+        return Some(format!("search for {pattern}"));
+    }
+
+    if let Some(pattern) = keys.strip_prefix('?') {
+        return Some(format!("search backwards for {pattern}"));
+    }
+
+    let command = keys.strip_prefix(':')?;
+
+    if let Some(file) = command.strip_prefix("e ") {
+        return Some(format!("open {}", file.trim()));
+    }
+
+    if let Some(file) = command.strip_prefix("w ") {
+        return Some(format!("save as {}", file.trim()));
+    }
+
+    if command.starts_with("s/") || command.starts_with("%s/") {
+        return Some("substitute".to_string());
+    }
+
+    if !command.is_empty() && command.chars().all(|char| char.is_ascii_digit()) {
+        return Some(format!("go to line {command}"));
+    }
+
+    None
+}
+
+/// The character a key typed, from its name or a letter key with shift
+fn typed_char(key: rdev::Key, text: Option<&str>, held: &BTreeSet<Modifier>) -> Option<char> {
+    if let Some(text) = text {
+        let mut chars = text.chars();
+
+        return match (chars.next(), chars.next()) {
+            (Some(char), None) if !char.is_control() => Some(char),
+            _ => None,
+        };
+    }
+
+    // no name on some platforms, letters are enough for most commands
+    let name = format!("{key:?}");
+    let letter = name.strip_prefix("Key")?.chars().next()?;
+
+    Some(if held.contains(&Modifier::Shift) {
+        letter.to_ascii_uppercase()
+    } else {
+        letter.to_ascii_lowercase()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vim() -> Vim {
+        Vim::new(&VimConfig::default())
+    }
+
+    /// What Vim shows for each of the typed characters
+    fn type_keys(vim: &mut Vim, keys: &str) -> Vec<Output> {
+        keys.chars()
+            .map(|char| {
+                vim.press(
+                    rdev::Key::Unknown(0),
+                    Some(&char.to_string()),
+                    &BTreeSet::new(),
+                )
+            })
+            .collect()
+    }
+
+    fn command(keys: &str, description: &str) -> Output {
+        Output::Command {
+            keys: keys.to_string(),
+            description: Some(description.to_string()),
+        }
+    }
+
+    #[test]
+    fn operators_wait_for_a_motion() {
+        let mut vim = vim();
+
+        assert_eq!(
+            type_keys(&mut vim, "dw"),
+            [Output::Hidden, command("dw", "delete to the next word")]
+        );
+        assert_eq!(
+            type_keys(&mut vim, "dd"),
+            [Output::Hidden, command("dd", "delete the line")]
+        );
+    }
+
+    #[test]
+    fn counts_are_kept_with_the_command() {
+        let mut vim = vim();
+
+        assert_eq!(
+            type_keys(&mut vim, "3dw").last(),
+            Some(&command("3dw", "delete to the next word, 3 times"))
+        );
+        assert_eq!(
+            type_keys(&mut vim, "d3w").last(),
+            Some(&command("d3w", "delete to the next word, 3 times"))
+        );
+        assert_eq!(
+            type_keys(&mut vim, "12G").last(),
+            Some(&command("12G", "to line 12"))
+        );
+
+        // 0 alone is a motion, not a count
+        assert!(matches!(
+            type_keys(&mut vim, "0").last(),
+            Some(Output::Command { .. })
+        ));
+    }
+
+    #[test]
+    fn registers_come_before_the_command() {
+        let mut vim = vim();
+
+        assert_eq!(
+            type_keys(&mut vim, "\"ayy"),
+            [
+                Output::Hidden,
+                Output::Hidden,
+                Output::Hidden,
+                command("\"ayy", "yank the line")
+            ]
+        );
+    }
+
+    #[test]
+    fn change_enters_insert_mode_until_escape() {
+        let mut vim = vim();
+
+        assert_eq!(
+            type_keys(&mut vim, "ciw").last(),
+            Some(&command("ciw", "change inside word"))
+        );
+        assert_eq!(type_keys(&mut vim, "hello"), vec![Output::Hidden; 5]);
+
+        assert_eq!(
+            vim.press(rdev::Key::Escape, None, &BTreeSet::new()),
+            Output::Key
+        );
+        assert_eq!(
+            type_keys(&mut vim, "x"),
+            [command("x", "delete a character")]
+        );
+    }
+
+    #[test]
+    fn escape_drops_the_pending_operator() {
+        let mut vim = vim();
+
+        type_keys(&mut vim, "2d");
+        vim.press(rdev::Key::Escape, None, &BTreeSet::new());
+
+        assert_eq!(type_keys(&mut vim, "w"), [command("w", "to the next word")]);
+    }
+
+    #[test]
+    fn command_lines_end_with_enter() {
+        let mut vim = vim();
+
+        assert_eq!(type_keys(&mut vim, ":wq"), vec![Output::Hidden; 3]);
+        assert_eq!(
+            vim.press(rdev::Key::Return, None, &BTreeSet::new()),
+            command(":wq", "save and quit")
+        );
+    }
+
+    #[test]
+    fn descriptions_can_be_turned_off() {
+        let mut vim = Vim::new(&VimConfig {
+            descriptions: Some(false),
+            ..VimConfig::default()
+        });
+
+        assert_eq!(
+            type_keys(&mut vim, "dd").last(),
+            Some(&Output::Command {
+                keys: "dd".to_string(),
+                description: None
+            })
+        );
+    }
+}
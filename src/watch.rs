@@ -0,0 +1,100 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc as std_mpsc,
+    time::Duration,
+};
+
+use iced::{
+    futures::{channel::mpsc, StreamExt},
+    Subscription,
+};
+use iced_native::subscription;
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Editors write a file in a few steps, the changes within this are one
+const SETTLE_DELAY: Duration = Duration::from_millis(200);
+
+pub enum State {
+    Starting(PathBuf),
+    Ready(mpsc::UnboundedReceiver<()>),
+    Finished,
+}
+
+/// Fires when the file is written, replaced or created, once it settles
+pub fn changes(path: PathBuf) -> Subscription<()> {
+    struct Changes;
+
+    subscription::unfold(
+        std::any::TypeId::of::<Changes>(),
+        State::Starting(path),
+        |state| async move {
+            match state {
+                State::Starting(path) => {
+                    let (sender, receiver) = mpsc::unbounded();
+
+                    let (events, watched) = std_mpsc::channel();
+
+                    let watcher = match watch(&path, events) {
+                        Ok(watcher) => watcher,
+                        Err(e) => {
+                            eprintln!("Could not watch {}: {e}", path.display());
+                            return (None, State::Finished);
+                        }
+                    };
+
+                    std::thread::spawn(move || {
+                        // it stops watching when dropped
+                        let _watcher = watcher;
+
+                        while watched.recv().is_ok() {
+                            while watched.recv_timeout(SETTLE_DELAY).is_ok() {}
+
+                            // the app is closed
+                            if sender.unbounded_send(()).is_err() {
+                                return;
+                            }
+                        }
+                    });
+
+                    (None, State::Ready(receiver))
+                }
+                State::Ready(mut changes) => match changes.next().await {
+                    Some(()) => (Some(()), State::Ready(changes)),
+                    None => (None, State::Finished),
+                },
+                State::Finished => iced::futures::future::pending().await,
+            }
+        },
+    )
+}
+
+/// Sends on `events` when the file changes, while the watcher is kept
+fn watch(path: &Path, events: std_mpsc::Sender<()>) -> notify::Result<RecommendedWatcher> {
+    // editors often replace the file instead of writing it, so its directory is watched
+    let dir = path
+        .parent()
+        .ok_or_else(|| notify::Error::generic(&format!("{} has no directory", path.display())))?;
+
+    std::fs::create_dir_all(dir)?;
+
+    let file = path.to_path_buf();
+
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+
+        let changed = matches!(
+            event.kind,
+            EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+        ) && event.paths.contains(&file);
+
+        if changed {
+            let _ = events.send(());
+        }
+    })?;
+
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+
+    Ok(watcher)
+}
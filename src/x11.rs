@@ -0,0 +1,199 @@
+use std::{error::Error, time::Duration};
+
+use x11rb::{
+    connection::Connection,
+    protocol::{
+        shape::{ConnectionExt as _, SK, SO},
+        xproto::{Atom, AtomEnum, ClipOrdering, ConnectionExt as _, Window},
+    },
+    rust_connection::RustConnection,
+};
+
+/// How long to wait for the window to be created
+const WINDOW_TRIES: u32 = 20;
+const WINDOW_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Lets mouse input pass through the overlay to the windows below, or stops it,
+/// in the background since the window may not exist yet
+pub fn set_click_through(enabled: bool) {
+    std::thread::spawn(move || {
+        for _ in 0..WINDOW_TRIES {
+            match try_set_click_through(enabled) {
+                Ok(true) => return,
+                Ok(false) => std::thread::sleep(WINDOW_RETRY_DELAY),
+                Err(e) => {
+                    eprintln!("Could not change click-through: {e}");
+                    return;
+                }
+            }
+        }
+
+        eprintln!("Could not change click-through: the overlay window wasn't found");
+    });
+}
+
+/// Returns whether the window was found
+fn try_set_click_through(enabled: bool) -> Result<bool, Box<dyn Error>> {
+    let (connection, screen) = x11rb::connect(None)?;
+    let root = connection.setup().roots[screen].root;
+
+    let windows = own_windows(&connection, root)?;
+
+    for window in &windows {
+        if enabled {
+            // an empty input shape, so no mouse event lands on the window
+            connection.shape_rectangles(
+                SO::SET,
+                SK::INPUT,
+                ClipOrdering::UNSORTED,
+                *window,
+                0,
+                0,
+                &[],
+            )?;
+        } else {
+            connection.shape_mask(SO::SET, SK::INPUT, *window, 0, 0, x11rb::NONE)?;
+        }
+    }
+
+    connection.flush()?;
+
+    Ok(!windows.is_empty())
+}
+
+/// The windows of this process, found by their `_NET_WM_PID`
+/// among the windows the window manager lists in `_NET_CLIENT_LIST`
+fn own_windows(connection: &RustConnection, root: Window) -> Result<Vec<Window>, Box<dyn Error>> {
+    let client_list = connection.intern_atom(false, b"_NET_CLIENT_LIST")?;
+    let pid_atom = connection.intern_atom(false, b"_NET_WM_PID")?;
+    let (client_list, pid_atom) = (client_list.reply()?.atom, pid_atom.reply()?.atom);
+
+    let listed: Vec<Window> = connection
+        .get_property(false, root, client_list, AtomEnum::WINDOW, 0, u32::MAX)?
+        .reply()?
+        .value32()
+        .map(Iterator::collect)
+        .unwrap_or_default();
+
+    // without a window manager that keeps the list, the top-level windows
+    let candidates = if listed.is_empty() {
+        connection.query_tree(root)?.reply()?.children
+    } else {
+        listed
+    };
+
+    // all the requests go out before the first reply is waited for
+    let requests = candidates
+        .iter()
+        .map(|&window| connection.get_property(false, window, pid_atom, AtomEnum::CARDINAL, 0, 1))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let pid = std::process::id();
+    let mut windows = Vec::new();
+
+    for (window, request) in candidates.into_iter().zip(requests) {
+        // a window may be gone by now
+        let Ok(reply) = request.reply() else {
+            continue;
+        };
+
+        if reply.value32().and_then(|mut values| values.next()) == Some(pid) {
+            windows.push(window);
+        }
+    }
+
+    Ok(windows)
+}
+
+/// The focused window, as far as apps are told apart
+#[derive(Debug, Clone, Default)]
+pub struct FocusedWindow {
+    /// The instance and class names in its `WM_CLASS`, like "code" and "Code"
+    pub apps: Vec<String>,
+    /// From `_NET_WM_NAME` or the older `WM_NAME`
+    pub title: String,
+}
+
+/// Finds the focused window with EWMH `_NET_ACTIVE_WINDOW`
+pub struct Focus {
+    connection: RustConnection,
+    root: Window,
+    active_window: Atom,
+    wm_name: Atom,
+    utf8_string: Atom,
+}
+
+impl Focus {
+    pub fn connect() -> Result<Self, Box<dyn Error>> {
+        let (connection, screen) = x11rb::connect(None)?;
+        let root = connection.setup().roots[screen].root;
+
+        let atom = |name: &[u8]| -> Result<Atom, Box<dyn Error>> {
+            Ok(connection.intern_atom(false, name)?.reply()?.atom)
+        };
+
+        let active_window = atom(b"_NET_ACTIVE_WINDOW")?;
+        let wm_name = atom(b"_NET_WM_NAME")?;
+        let utf8_string = atom(b"UTF8_STRING")?;
+
+        Ok(Self {
+            connection,
+            root,
+            active_window,
+            wm_name,
+            utf8_string,
+        })
+    }
+
+    fn active_window(&self) -> Result<Option<Window>, Box<dyn Error>> {
+        let window = self
+            .connection
+            .get_property(false, self.root, self.active_window, AtomEnum::WINDOW, 0, 1)?
+            .reply()?
+            .value32()
+            .and_then(|mut values| values.next());
+
+        Ok(window.filter(|window| *window != x11rb::NONE))
+    }
+
+    /// The focused window's app and title, asked for together
+    pub fn window(&self) -> Result<FocusedWindow, Box<dyn Error>> {
+        let Some(window) = self.active_window()? else {
+            return Ok(FocusedWindow::default());
+        };
+
+        let class = self.connection.get_property(
+            false,
+            window,
+            AtomEnum::WM_CLASS,
+            AtomEnum::STRING,
+            0,
+            1024,
+        )?;
+        let name =
+            self.connection
+                .get_property(false, window, self.wm_name, self.utf8_string, 0, 1024)?;
+
+        let class = class.reply()?.value;
+        let mut title = name.reply()?.value;
+
+        // the older WM_NAME, only asked for when there's no _NET_WM_NAME
+        if title.is_empty() {
+            title = self
+                .connection
+                .get_property(false, window, AtomEnum::WM_NAME, AtomEnum::ANY, 0, 1024)?
+                .reply()?
+                .value;
+        }
+
+        Ok(FocusedWindow {
+            // two strings, each ending with a null byte
+            apps: class
+                .split(|byte| *byte == 0)
+                .filter(|name| !name.is_empty())
+                .map(|name| String::from_utf8_lossy(name).into_owned())
+                .collect(),
+            title: String::from_utf8_lossy(&title).into_owned(),
+        })
+    }
+}